@@ -0,0 +1,336 @@
+use crypto_math::{from_slice, Keypair};
+use num::{
+    bigint::{BigInt, Sign},
+    Integer, One,
+};
+use rand::{Rng, SeedableRng, StdRng};
+use std::cell::Cell;
+use wasm_bindgen::prelude::*;
+
+// PKCS#1 v1.5 needs 00 02, at least eight padding bytes and a 00 separator around the message
+const MIN_PADDING: usize = 11;
+
+// EM = 00 02 PS 00 M, with PS at least eight random non-zero bytes, filling k bytes in total.
+// Returns None when the message doesn't fit.
+// Ref: https://tools.ietf.org/html/rfc8017#section-7.2.1
+pub fn pkcs1_pad(message: &[u8], k: usize, seed: &[u8]) -> Option<Vec<u8>> {
+    if message.len() + MIN_PADDING > k {
+        return None;
+    }
+
+    let mut rng: StdRng = SeedableRng::from_seed(from_slice(seed));
+
+    let mut padded = vec![0, 2];
+    for _ in 0..k - message.len() - 3 {
+        padded.push(rng.gen_range(1, 256) as u8);
+    }
+    padded.push(0);
+    padded.extend_from_slice(message);
+
+    Some(padded)
+}
+
+// Undoes pkcs1_pad, None unless the block has the exact layout it produces
+pub fn pkcs1_unpad(padded: &[u8]) -> Option<Vec<u8>> {
+    if padded.len() < MIN_PADDING || padded[0] != 0 || padded[1] != 2 {
+        return None;
+    }
+
+    let separator = padded[2..].iter().position(|&b| b == 0)? + 2;
+    if separator < 10 {
+        return None;
+    }
+
+    Some(padded[separator + 1..].to_vec())
+}
+
+#[cfg(test)]
+mod test_pkcs1_padding {
+    use super::*;
+    use crypto_math::test_seed;
+
+    #[test]
+    fn round_trip() {
+        let padded = pkcs1_pad(b"hello", 32, test_seed()).unwrap();
+
+        assert_eq!(padded.len(), 32);
+        assert_eq!(&padded[..2], &[0, 2]);
+        assert!(padded[2..26].iter().all(|&b| b != 0));
+        assert_eq!(pkcs1_unpad(&padded), Some(b"hello".to_vec()));
+    }
+
+    #[test]
+    fn too_long() {
+        assert_eq!(pkcs1_pad(b"hello", 15, test_seed()), None);
+        assert!(pkcs1_pad(b"hello", 16, test_seed()).is_some());
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(pkcs1_unpad(&[0, 1, 9, 9, 9, 9, 9, 9, 9, 9, 0, 65]), None);
+        // Only seven padding bytes
+        assert_eq!(pkcs1_unpad(&[0, 2, 9, 9, 9, 9, 9, 9, 9, 0, 65, 66]), None);
+        assert_eq!(
+            pkcs1_unpad(&[0, 2, 9, 9, 9, 9, 9, 9, 9, 9, 0, 65]),
+            Some(vec![65])
+        );
+    }
+}
+
+// A server that decrypts PKCS#1 v1.5 ciphertexts and lets slip whether the padding was valid,
+// which is all Bleichenbacher's attack needs. Every question asked of it is counted.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct PaddingOracle {
+    keypair: Keypair,
+    // Length of n in bytes, the size of every padded block
+    k: usize,
+    // Calls to is_conforming so far, stuck at u32::MAX rather than wrapping
+    queries: Cell<u32>,
+}
+
+#[wasm_bindgen]
+impl PaddingOracle {
+    // Small primes keep the attack down to seconds, 64-bit primes already leave room for a five
+    // byte message. e is 65537 as with real PKCS#1 keys.
+    pub fn new(seed_one: &[u8], seed_two: &[u8], prime_bits: u32) -> PaddingOracle {
+        let keypair = Keypair::generate(seed_one, seed_two, prime_bits as usize, Some(65537));
+        let k = keypair.modulus().to_bytes_be().1.len();
+        assert!(
+            k > MIN_PADDING,
+            "the modulus is too small for PKCS#1 v1.5 padding"
+        );
+
+        PaddingOracle {
+            keypair,
+            k,
+            queries: Cell::new(0),
+        }
+    }
+
+    pub fn public_key_display_wasm(&self) -> String {
+        format!(
+            "({}, {})",
+            self.keypair.public_exponent(),
+            self.keypair.modulus()
+        )
+    }
+
+    // Pads and encrypts message as a single number
+    pub fn encrypt(&self, message: &str, seed: &[u8]) -> String {
+        let padded = pkcs1_pad(message.as_bytes(), self.k, seed).expect("message too long");
+        let m = BigInt::from_bytes_be(Sign::Plus, &padded);
+
+        format!(
+            "{}",
+            m.modpow(&self.keypair.public_exponent(), &self.keypair.modulus())
+        )
+    }
+
+    pub fn queries(&self) -> u32 {
+        self.queries.get()
+    }
+
+    // Runs bleichenbacher_attack on ciphertext and returns the message, or an empty string if the
+    // ciphertext wasn't PKCS#1 conforming to begin with
+    pub fn attack(&self, ciphertext: &str) -> String {
+        let c = BigInt::parse_bytes(ciphertext.as_bytes(), 10).unwrap();
+
+        bleichenbacher_attack(self, &c)
+            .and_then(|m| pkcs1_unpad(&self.to_block(&m)))
+            .map(|message| String::from_utf8_lossy(&message).into_owned())
+            .unwrap_or_default()
+    }
+}
+
+impl PaddingOracle {
+    // The oracle itself: whether c decrypts to a block starting 00 02. Real implementations
+    // leaked this through distinct error messages or timing.
+    pub fn is_conforming(&self, c: &BigInt) -> bool {
+        self.queries.set(self.queries.get().saturating_add(1));
+
+        let block = self.to_block(&self.keypair.decrypt_num(c));
+        block[0] == 0 && block[1] == 2
+    }
+
+    // What the server would do with a real message, None for bad padding
+    pub fn decrypt(&self, c: &BigInt) -> Option<Vec<u8>> {
+        pkcs1_unpad(&self.to_block(&self.keypair.decrypt_num(c)))
+    }
+
+    pub fn public_exponent(&self) -> BigInt {
+        self.keypair.public_exponent()
+    }
+
+    pub fn modulus(&self) -> BigInt {
+        self.keypair.modulus()
+    }
+
+    // m as exactly k big-endian bytes
+    fn to_block(&self, m: &BigInt) -> Vec<u8> {
+        let (_, bytes) = m.to_bytes_be();
+        let mut block = vec![0; self.k - bytes.len()];
+        block.extend_from_slice(&bytes);
+        block
+    }
+}
+
+fn ceil_div(a: &BigInt, b: &BigInt) -> BigInt {
+    -(-a).div_floor(b)
+}
+
+// Step 3: every conforming m s lies in [2B, 3B) mod n, which cuts each interval m could be in down
+// to the pieces compatible with some wrap-around count r
+fn narrow(
+    intervals: &[(BigInt, BigInt)],
+    s: &BigInt,
+    n: &BigInt,
+    two_b: &BigInt,
+    three_b: &BigInt,
+) -> Vec<(BigInt, BigInt)> {
+    let mut narrowed = Vec::new();
+
+    for (a, b) in intervals {
+        let mut r = ceil_div(&(a * s - three_b + BigInt::one()), n);
+        let r_max = (b * s - two_b).div_floor(n);
+
+        while r <= r_max {
+            let low = ceil_div(&(two_b + &r * n), s).max(a.clone());
+            let high = (three_b - BigInt::one() + &r * n)
+                .div_floor(s)
+                .min(b.clone());
+            if low <= high {
+                narrowed.push((low, high));
+            }
+            r += 1;
+        }
+    }
+
+    // Merge overlapping pieces
+    narrowed.sort();
+    let mut merged: Vec<(BigInt, BigInt)> = Vec::new();
+    for (low, high) in narrowed {
+        if let Some(last) = merged.last_mut() {
+            if low <= last.1 {
+                if high > last.1 {
+                    last.1 = high;
+                }
+                continue;
+            }
+        }
+        merged.push((low, high));
+    }
+
+    merged
+}
+
+// Bleichenbacher's attack: multiplying c by s^e multiplies the plaintext by s, and every time the
+// oracle says m s is conforming the interval m can be in shrinks, until only m itself is left.
+// Returns the padded block as a number, or None if c isn't conforming.
+// Ref: Bleichenbacher, Chosen Ciphertext Attacks Against Protocols Based on the RSA Encryption
+// Standard PKCS #1, CRYPTO '98
+pub fn bleichenbacher_attack(oracle: &PaddingOracle, c: &BigInt) -> Option<BigInt> {
+    let n = oracle.modulus();
+    let e = oracle.public_exponent();
+
+    // Step 1: c comes from encrypt so it's conforming already, no blinding needed
+    if !oracle.is_conforming(c) {
+        return None;
+    }
+
+    let b = BigInt::one() << (8 * (oracle.k - 2));
+    let two_b = &b * 2u32;
+    let three_b = &b * 3u32;
+
+    let conforming = |s: &BigInt| oracle.is_conforming(&(c * s.modpow(&e, &n) % &n));
+
+    let mut intervals = vec![(two_b.clone(), &three_b - BigInt::one())];
+
+    // Step 2a: the smallest s that could possibly work is n / 3B
+    let mut s = ceil_div(&n, &three_b);
+    while !conforming(&s) {
+        s += 1;
+    }
+
+    loop {
+        intervals = narrow(&intervals, &s, &n, &two_b, &three_b);
+
+        if intervals.is_empty() {
+            return None;
+        }
+        if intervals.len() == 1 && intervals[0].0 == intervals[0].1 {
+            return Some(intervals[0].0.clone());
+        }
+
+        if intervals.len() > 1 {
+            // Step 2b: several intervals left, keep counting up
+            s += 1;
+            while !conforming(&s) {
+                s += 1;
+            }
+        } else {
+            // Step 2c: one interval [a, b], try small r with s chosen to land m s r n in [2B, 3B)
+            let (ref a, ref b) = intervals[0];
+            let mut r = ceil_div(&((b * &s - &two_b) * 2u32), &n);
+
+            'search: loop {
+                let mut candidate = ceil_div(&(&two_b + &r * &n), b);
+                let high = ceil_div(&(&three_b + &r * &n), a);
+
+                while candidate < high {
+                    if conforming(&candidate) {
+                        s = candidate;
+                        break 'search;
+                    }
+                    candidate += 1;
+                }
+
+                r += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_bleichenbacher_attack {
+    use super::*;
+    use crypto_math::test_seed;
+
+    fn oracle() -> PaddingOracle {
+        PaddingOracle::new(test_seed(), &[5; 32], 64)
+    }
+
+    #[test]
+    fn oracle_counts_queries() {
+        let oracle = oracle();
+        let c = BigInt::parse_bytes(oracle.encrypt("hi", &[3; 32]).as_bytes(), 10).unwrap();
+
+        assert_eq!(oracle.decrypt(&c), Some(b"hi".to_vec()));
+        assert!(oracle.is_conforming(&c));
+        assert!(!oracle.is_conforming(&BigInt::one()));
+        assert_eq!(oracle.queries(), 2);
+
+        // The count saturates instead of wrapping back to zero
+        oracle.queries.set(4294967294);
+        oracle.is_conforming(&c);
+        oracle.is_conforming(&c);
+        assert_eq!(oracle.queries(), 4294967295);
+    }
+
+    #[test]
+    fn recovers_message() {
+        let oracle = oracle();
+        let ciphertext = oracle.encrypt("yes!", &[3; 32]);
+
+        assert_eq!(oracle.attack(&ciphertext), "yes!");
+        // Far more than one query, but nothing like the 2^128 of guessing the padded block
+        assert!(oracle.queries() > 100);
+    }
+
+    #[test]
+    fn non_conforming() {
+        let oracle = oracle();
+        assert_eq!(oracle.attack("1"), "");
+        assert_eq!(oracle.queries(), 1);
+    }
+}
@@ -0,0 +1,314 @@
+// Historical ciphers over the 26 letter alphabet, as a warm-up before RSA. Caesar, affine,
+// Vigenère and autokey keep the case of letters and pass everything else through untouched. Hill
+// works on blocks of letters, so it drops everything else and pads the last block with X.
+// Ref: https://en.wikipedia.org/wiki/Classical_cipher
+
+use crypto_math::{gcd, mod_inverse};
+use matrix::ModMatrix;
+use num::bigint::BigInt;
+use num_traits::ToPrimitive;
+use wasm_bindgen::prelude::*;
+
+const ALPHABET: i64 = 26;
+
+// Reduces into [0, 26) whatever the sign
+pub(crate) fn letter_mod(x: i64) -> i64 {
+    (x % ALPHABET + ALPHABET) % ALPHABET
+}
+
+// Applies f to the 0-25 value of every letter in text, keeping its case
+pub(crate) fn map_letters<F: FnMut(i64) -> i64>(text: &str, mut f: F) -> String {
+    text.chars()
+        .map(|c| {
+            if !c.is_ascii_alphabetic() {
+                return c;
+            }
+            let base = if c.is_ascii_uppercase() { b'A' } else { b'a' };
+            let value = f((c as u8 - base) as i64);
+            (base + letter_mod(value) as u8) as char
+        })
+        .collect()
+}
+
+// The letters of a key as 0-25 values, ignoring case and anything else
+pub(crate) fn key_values(key: &str) -> Vec<i64> {
+    key.chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| (c.to_ascii_uppercase() as u8 - b'A') as i64)
+        .collect()
+}
+
+// a^-1 mod 26, the affine and Hill ciphers need it to decrypt
+pub(crate) fn inverse_mod_26(a: i64) -> Option<i64> {
+    mod_inverse(&letter_mod(a).to_string(), &ALPHABET.to_string())
+        .map(|inverse| letter_mod(inverse.parse().unwrap()))
+}
+
+#[wasm_bindgen]
+pub fn caesar_encrypt(text: &str, shift: u32) -> String {
+    map_letters(text, |x| x + shift as i64)
+}
+
+#[wasm_bindgen]
+pub fn caesar_decrypt(text: &str, shift: u32) -> String {
+    map_letters(text, |x| x - shift as i64)
+}
+
+#[cfg(test)]
+mod test_caesar {
+    use super::*;
+
+    #[test]
+    fn small() {
+        // Ref: https://en.wikipedia.org/wiki/Caesar_cipher#Example
+        let plaintext = "THE QUICK BROWN FOX JUMPS OVER THE LAZY DOG";
+        let ciphertext = "QEB NRFZH YOLTK CLU GRJMP LSBO QEB IXWV ALD";
+        assert_eq!(caesar_encrypt(plaintext, 23), ciphertext);
+        assert_eq!(caesar_decrypt(ciphertext, 23), plaintext);
+    }
+
+    #[test]
+    fn keeps_case_and_punctuation() {
+        assert_eq!(caesar_encrypt("Hello, World!", 3), "Khoor, Zruog!");
+        assert_eq!(caesar_encrypt("xyz", 29), "abc");
+    }
+}
+
+// x -> a x + b mod 26, a has to be coprime to 26 for this to be decryptable
+#[wasm_bindgen]
+pub fn affine_encrypt(text: &str, a: u32, b: u32) -> String {
+    assert!(
+        gcd(&a.to_string(), &ALPHABET.to_string()) == "1",
+        "a has to be coprime to 26"
+    );
+    map_letters(text, |x| a as i64 * x + b as i64)
+}
+
+#[wasm_bindgen]
+pub fn affine_decrypt(text: &str, a: u32, b: u32) -> String {
+    let a_inverse = inverse_mod_26(a as i64).expect("a has to be coprime to 26");
+    map_letters(text, |x| a_inverse * (x - b as i64))
+}
+
+#[cfg(test)]
+mod test_affine {
+    use super::*;
+
+    #[test]
+    fn small() {
+        // Ref: https://en.wikipedia.org/wiki/Affine_cipher#Encrypting
+        assert_eq!(affine_encrypt("AFFINE CIPHER", 5, 8), "IHHWVC SWFRCP");
+        assert_eq!(affine_decrypt("IHHWVC SWFRCP", 5, 8), "AFFINE CIPHER");
+    }
+
+    #[test]
+    fn caesar_is_a_equal_one() {
+        assert_eq!(affine_encrypt("Hello", 1, 3), caesar_encrypt("Hello", 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "coprime to 26")]
+    fn not_coprime() {
+        affine_encrypt("Hello", 13, 3);
+    }
+}
+
+// Caesar with the shift taken from the next letter of the key, which only advances on letters
+#[wasm_bindgen]
+pub fn vigenere_encrypt(text: &str, key: &str) -> String {
+    let key = key_values(key);
+    assert!(!key.is_empty(), "the key needs at least one letter");
+
+    let mut position = 0;
+    map_letters(text, |x| {
+        position += 1;
+        x + key[(position - 1) % key.len()]
+    })
+}
+
+#[wasm_bindgen]
+pub fn vigenere_decrypt(text: &str, key: &str) -> String {
+    let key = key_values(key);
+    assert!(!key.is_empty(), "the key needs at least one letter");
+
+    let mut position = 0;
+    map_letters(text, |x| {
+        position += 1;
+        x - key[(position - 1) % key.len()]
+    })
+}
+
+#[cfg(test)]
+mod test_vigenere {
+    use super::*;
+
+    #[test]
+    fn small() {
+        // Ref: https://en.wikipedia.org/wiki/Vigen%C3%A8re_cipher#Description
+        assert_eq!(vigenere_encrypt("ATTACKATDAWN", "LEMON"), "LXFOPVEFRNHR");
+        assert_eq!(vigenere_decrypt("LXFOPVEFRNHR", "LEMON"), "ATTACKATDAWN");
+    }
+
+    #[test]
+    fn skips_non_letters() {
+        assert_eq!(
+            vigenere_encrypt("Attack at dawn!", "lemon"),
+            "Lxfopv ef rnhr!"
+        );
+        assert_eq!(
+            vigenere_decrypt("Lxfopv ef rnhr!", "lemon"),
+            "Attack at dawn!"
+        );
+    }
+}
+
+// Vigenère where the key is only a primer and the plaintext itself continues the key stream
+// Ref: https://en.wikipedia.org/wiki/Autokey_cipher
+#[wasm_bindgen]
+pub fn autokey_encrypt(text: &str, key: &str) -> String {
+    let mut stream = key_values(key);
+    assert!(!stream.is_empty(), "the key needs at least one letter");
+
+    let mut position = 0;
+    map_letters(text, |x| {
+        stream.push(x);
+        position += 1;
+        x + stream[position - 1]
+    })
+}
+
+#[wasm_bindgen]
+pub fn autokey_decrypt(text: &str, key: &str) -> String {
+    let mut stream = key_values(key);
+    assert!(!stream.is_empty(), "the key needs at least one letter");
+
+    let mut position = 0;
+    map_letters(text, |x| {
+        let plain = letter_mod(x - stream[position]);
+        stream.push(plain);
+        position += 1;
+        plain
+    })
+}
+
+#[cfg(test)]
+mod test_autokey {
+    use super::*;
+
+    #[test]
+    fn small() {
+        assert_eq!(autokey_encrypt("ATTACKATDAWN", "QUEENLY"), "QNXEPVYTWTWP");
+        assert_eq!(autokey_decrypt("QNXEPVYTWTWP", "QUEENLY"), "ATTACKATDAWN");
+    }
+
+    #[test]
+    fn keeps_case_and_punctuation() {
+        let ciphertext = autokey_encrypt("Meet me at noon, not later.", "Key");
+        assert_eq!(ciphertext.len(), 27);
+        assert_eq!(
+            autokey_decrypt(&ciphertext, "Key"),
+            "Meet me at noon, not later."
+        );
+    }
+}
+
+// The square matrix a Hill key spells out row by row, e.g. GYBNQKURP for a 3 x 3 key
+pub(crate) fn hill_matrix(key: &str) -> Vec<Vec<i64>> {
+    let values = key_values(key);
+    let size = (1..values.len() + 1)
+        .find(|&k| k * k >= values.len())
+        .unwrap_or(0);
+    assert!(
+        size > 0 && size * size == values.len(),
+        "a Hill key needs a square number of letters"
+    );
+
+    values.chunks(size).map(|row| row.to_vec()).collect()
+}
+
+// The inverse mod 26, None when the determinant shares a factor with 26
+// Ref: https://en.wikipedia.org/wiki/Hill_cipher#Decryption
+pub(crate) fn matrix_inverse_mod_26(matrix: &[Vec<i64>]) -> Option<Vec<Vec<i64>>> {
+    let rows: Vec<Vec<BigInt>> = matrix
+        .iter()
+        .map(|row| row.iter().map(|&x| BigInt::from(x)).collect())
+        .collect();
+    let inverse = ModMatrix::new(&rows, &BigInt::from(ALPHABET)).inverse()?;
+
+    Some(
+        inverse
+            .rows()
+            .iter()
+            .map(|row| row.iter().map(|x| x.to_i64().unwrap()).collect())
+            .collect(),
+    )
+}
+
+// Multiplies every block of letters, as a column vector, by matrix
+pub(crate) fn hill_apply(text: &str, matrix: &[Vec<i64>]) -> String {
+    let mut values = key_values(text);
+    let padding = (matrix.len() - values.len() % matrix.len()) % matrix.len();
+    values.extend(vec![(b'X' - b'A') as i64; padding]);
+
+    values
+        .chunks(matrix.len())
+        .flat_map(|block| {
+            matrix
+                .iter()
+                .map(|row| {
+                    let x: i64 = row.iter().zip(block.iter()).map(|(a, b)| a * b).sum();
+                    (b'A' + letter_mod(x) as u8) as char
+                })
+                .collect::<Vec<char>>()
+        })
+        .collect()
+}
+
+// Ref: https://en.wikipedia.org/wiki/Hill_cipher
+#[wasm_bindgen]
+pub fn hill_encrypt(text: &str, key: &str) -> String {
+    let matrix = hill_matrix(key);
+    assert!(
+        matrix_inverse_mod_26(&matrix).is_some(),
+        "the key matrix has to be invertible mod 26"
+    );
+    hill_apply(text, &matrix)
+}
+
+#[wasm_bindgen]
+pub fn hill_decrypt(text: &str, key: &str) -> String {
+    let inverse = matrix_inverse_mod_26(&hill_matrix(key))
+        .expect("the key matrix has to be invertible mod 26");
+    hill_apply(text, &inverse)
+}
+
+#[cfg(test)]
+mod test_hill {
+    use super::*;
+
+    #[test]
+    fn small() {
+        assert_eq!(hill_encrypt("ACT", "GYBNQKURP"), "POH");
+        assert_eq!(hill_encrypt("cat", "GYBNQKURP"), "FIN");
+        assert_eq!(hill_decrypt("POH", "GYBNQKURP"), "ACT");
+    }
+
+    #[test]
+    fn inverse() {
+        // Ref: https://en.wikipedia.org/wiki/Hill_cipher#Decryption
+        assert_eq!(
+            matrix_inverse_mod_26(&hill_matrix("GYBNQKURP")),
+            Some(vec![vec![8, 5, 10], vec![21, 8, 21], vec![21, 12, 8]])
+        );
+        // det = 4, not invertible mod 26
+        assert_eq!(matrix_inverse_mod_26(&hill_matrix("CCBD")), None);
+    }
+
+    #[test]
+    fn pads_with_x() {
+        let ciphertext = hill_encrypt("Short message!", "HILL");
+        assert_eq!(ciphertext.len(), 12);
+        assert_eq!(hill_decrypt(&ciphertext, "HILL"), "SHORTMESSAGE");
+        assert_eq!(hill_decrypt(&hill_encrypt("odd", "HILL"), "HILL"), "ODDX");
+    }
+}
@@ -0,0 +1,584 @@
+// Breaking the ciphers in classical without their keys. Caesar and affine have so few keys that
+// every one of them can be tried and ranked by how English the result looks, Vigenère first needs
+// its key length from the index of coincidence and Kasiski examination and then falls apart into
+// one Caesar cipher per key letter. Hill is linear, so enough known plaintext gives the key away.
+// Ref: https://en.wikipedia.org/wiki/Frequency_analysis
+
+use classical::{
+    affine_decrypt, caesar_decrypt, hill_apply, inverse_mod_26, key_values, letter_mod,
+    matrix_inverse_mod_26, vigenere_decrypt,
+};
+use wasm_bindgen::prelude::*;
+
+// Percentages of A to Z in English text
+// Ref: https://en.wikipedia.org/wiki/Letter_frequency
+static ENGLISH_LETTER_FREQUENCIES: [f64; 26] = [
+    8.167, 1.492, 2.782, 4.253, 12.702, 2.228, 2.015, 6.094, 6.966, 0.153, 0.772, 4.025, 2.406,
+    6.749, 7.507, 1.929, 0.095, 5.987, 6.327, 9.056, 2.758, 0.978, 2.360, 0.150, 1.974, 0.074,
+];
+
+// How many key lengths from each of Kasiski and the index of coincidence get a full attempt
+const VIGENERE_LENGTH_GUESSES: usize = 3;
+
+// Hill known-plaintext only looks for an invertible set of blocks among the first few
+const HILL_MAX_BLOCKS: usize = 12;
+
+// How often each letter A to Z appears, ignoring case and everything but letters
+pub fn letter_counts(text: &str) -> [usize; 26] {
+    let mut counts = [0; 26];
+    for x in key_values(text) {
+        counts[x as usize] += 1;
+    }
+    counts
+}
+
+// Letter counts next to the English frequencies, as JSON for the client to chart
+#[wasm_bindgen]
+pub fn frequency_analysis(text: &str) -> String {
+    let counts = letter_counts(text);
+    let total: usize = counts.iter().sum();
+
+    let letters: Vec<String> = counts
+        .iter()
+        .zip(ENGLISH_LETTER_FREQUENCIES.iter())
+        .enumerate()
+        .map(|(i, (&count, &english))| {
+            let percent = if total == 0 {
+                0.0
+            } else {
+                100.0 * count as f64 / total as f64
+            };
+            format!(
+                "{{\"letter\":\"{}\",\"count\":{},\"percent\":{:.3},\"english\":{:.3}}}",
+                (b'A' + i as u8) as char,
+                count,
+                percent,
+                english
+            )
+        })
+        .collect();
+
+    format!("[{}]", letters.join(","))
+}
+
+#[cfg(test)]
+mod test_frequency_analysis {
+    use super::*;
+
+    #[test]
+    fn tiny() {
+        let counts = letter_counts("Hello, World!");
+        assert_eq!(counts[(b'L' - b'A') as usize], 3);
+        assert_eq!(counts.iter().sum::<usize>(), 10);
+
+        let json = frequency_analysis("Hello, World!");
+        assert!(
+            json.starts_with("[{\"letter\":\"A\",\"count\":0,\"percent\":0.000,\"english\":8.167}")
+        );
+        assert!(
+            json.contains("{\"letter\":\"L\",\"count\":3,\"percent\":30.000,\"english\":4.025}")
+        );
+        assert_eq!(json.matches("\"letter\"").count(), 26);
+    }
+}
+
+// The chance that two letters picked from text are the same: about 0.066 for English and 0.038
+// for uniformly random letters
+// Ref: https://en.wikipedia.org/wiki/Index_of_coincidence
+#[wasm_bindgen]
+pub fn index_of_coincidence(text: &str) -> f64 {
+    let counts = letter_counts(text);
+    let total: usize = counts.iter().sum();
+    if total < 2 {
+        return 0.0;
+    }
+
+    let pairs: usize = counts.iter().map(|&n| n * n.saturating_sub(1)).sum();
+    pairs as f64 / (total * (total - 1)) as f64
+}
+
+#[cfg(test)]
+mod test_index_of_coincidence {
+    use super::*;
+
+    #[test]
+    fn tiny() {
+        assert_eq!(index_of_coincidence(""), 0.0);
+        assert_eq!(index_of_coincidence("AAAA"), 1.0);
+        assert_eq!(index_of_coincidence("ABCD"), 0.0);
+        // 2 * 1 pairs of A and 2 * 1 of B out of 4 * 3
+        assert!((index_of_coincidence("a b a b") - 1.0 / 3.0).abs() < 1e-9);
+    }
+}
+
+// Pearson's chi-squared statistic of the letters in text against English, lower is more English.
+// Zero for text without letters.
+// Ref: https://en.wikipedia.org/wiki/Pearson%27s_chi-squared_test
+#[wasm_bindgen]
+pub fn chi_squared(text: &str) -> f64 {
+    let counts = letter_counts(text);
+    let total = counts.iter().sum::<usize>() as f64;
+    if total == 0.0 {
+        return 0.0;
+    }
+
+    counts
+        .iter()
+        .zip(ENGLISH_LETTER_FREQUENCIES.iter())
+        .map(|(&count, &percent)| {
+            let expected = total * percent / 100.0;
+            (count as f64 - expected).powi(2) / expected
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod test_chi_squared {
+    use super::*;
+
+    #[test]
+    fn small() {
+        let english = "It was the best of times, it was the worst of times";
+        assert!(chi_squared(english) < chi_squared(&caesar_decrypt(english, 7)));
+        assert_eq!(chi_squared("1234"), 0.0);
+    }
+}
+
+// Spacings between repeats of every three letter sequence, and for each key length up to
+// max_key_length how many of those spacings it divides. A Vigenère key lines up the same way
+// whenever a repeated word sits a multiple of its length apart, so the true length, and its
+// divisors, collect the most. Sorted by count, shorter lengths first among equals.
+// Ref: https://en.wikipedia.org/wiki/Kasiski_examination
+pub fn kasiski_examination(text: &str, max_key_length: usize) -> Vec<(usize, usize)> {
+    let letters = key_values(text);
+
+    let mut spacings = Vec::new();
+    for i in 0..letters.len().saturating_sub(2) {
+        let repeat = (i + 1..letters.len() - 2).find(|&j| letters[j..j + 3] == letters[i..i + 3]);
+        if let Some(j) = repeat {
+            spacings.push(j - i);
+        }
+    }
+
+    // No spacing is as long as the text, so neither is any length worth counting
+    let max_key_length = max_key_length.min(letters.len());
+    let mut counts: Vec<(usize, usize)> = (2..=max_key_length)
+        .map(|length| {
+            let count = spacings.iter().filter(|&&s| s % length == 0).count();
+            (length, count)
+        })
+        .collect();
+    counts.sort_by_key(|&(length, count)| (::std::cmp::Reverse(count), length));
+
+    counts
+}
+
+#[cfg(test)]
+mod test_kasiski_examination {
+    use super::*;
+
+    #[test]
+    fn small() {
+        // Ref: https://en.wikipedia.org/wiki/Kasiski_examination#How_it_works, the two CSASTP
+        // are 16 apart
+        let ranked = kasiski_examination("CSASTPKVSIQUTGQUCSASTPIUAQJB", 8);
+        assert_eq!(ranked[0], (2, 4));
+        assert_eq!(&ranked[1..3], &[(4, 4), (8, 4)]);
+        assert_eq!(ranked[3].1, 0);
+    }
+}
+
+// Average index of coincidence of the columns text splits into for each key length. Columns of
+// a correctly guessed length are each a Caesar cipher and look like English.
+pub fn vigenere_key_lengths(text: &str, max_key_length: usize) -> Vec<(usize, f64)> {
+    let letters = key_values(text);
+    // Past the letter count every column is a single letter or empty
+    let max_key_length = max_key_length.min(letters.len());
+
+    (1..=max_key_length)
+        .map(|length| {
+            let total: f64 = (0..length)
+                .map(|column| {
+                    let column: String = letters
+                        .iter()
+                        .skip(column)
+                        .step_by(length)
+                        .map(|&x| (b'A' + x as u8) as char)
+                        .collect();
+                    index_of_coincidence(&column)
+                })
+                .sum();
+            (length, total / length as f64)
+        })
+        .collect()
+}
+
+// A guessed key with what it decrypts to and its chi_squared score
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    pub key: String,
+    pub plaintext: String,
+    pub score: f64,
+}
+
+fn ranked(mut candidates: Vec<Candidate>) -> Vec<Candidate> {
+    candidates.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap());
+    candidates
+}
+
+// Quotes s for JSON, escaping what can't appear in a string literal
+fn json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// The best count candidates as JSON, best first
+fn candidates_json(candidates: &[Candidate], count: u32) -> String {
+    let entries: Vec<String> = candidates
+        .iter()
+        .take(count as usize)
+        .map(|c| {
+            format!(
+                "{{\"key\":{},\"plaintext\":{},\"score\":{:.3}}}",
+                json_string(&c.key),
+                json_string(&c.plaintext),
+                c.score
+            )
+        })
+        .collect();
+
+    format!("[{}]", entries.join(","))
+}
+
+// All 26 shifts, best first. The key is the shift.
+pub fn caesar_candidates(text: &str) -> Vec<Candidate> {
+    ranked(
+        (0..26u32)
+            .map(|shift| {
+                let plaintext = caesar_decrypt(text, shift);
+                Candidate {
+                    key: shift.to_string(),
+                    score: chi_squared(&plaintext),
+                    plaintext,
+                }
+            })
+            .collect(),
+    )
+}
+
+#[wasm_bindgen]
+pub fn break_caesar(text: &str, count: u32) -> String {
+    candidates_json(&caesar_candidates(text), count)
+}
+
+#[cfg(test)]
+mod test_break_caesar {
+    use super::*;
+    use classical::caesar_encrypt;
+
+    #[test]
+    fn small() {
+        let plaintext = "Defend the east wall of the castle";
+        let candidates = caesar_candidates(&caesar_encrypt(plaintext, 11));
+
+        assert_eq!(candidates.len(), 26);
+        assert_eq!(candidates[0].key, "11");
+        assert_eq!(candidates[0].plaintext, plaintext);
+    }
+
+    #[test]
+    fn json() {
+        let ciphertext = caesar_encrypt("He said \"meet at the station at seven\"", 3);
+        let json = break_caesar(&ciphertext, 1);
+        assert!(json.starts_with(
+            "[{\"key\":\"3\",\"plaintext\":\"He said \\\"meet at the station at seven\\\"\",\"score\":"
+        ));
+        assert_eq!(json.matches("\"key\"").count(), 1);
+    }
+}
+
+// All 312 keys with a coprime to 26, best first. The key is "a,b".
+pub fn affine_candidates(text: &str) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+
+    for a in (1..26u32).filter(|&a| inverse_mod_26(a as i64).is_some()) {
+        for b in 0..26u32 {
+            let plaintext = affine_decrypt(text, a, b);
+            candidates.push(Candidate {
+                key: format!("{},{}", a, b),
+                score: chi_squared(&plaintext),
+                plaintext,
+            });
+        }
+    }
+
+    ranked(candidates)
+}
+
+#[wasm_bindgen]
+pub fn break_affine(text: &str, count: u32) -> String {
+    candidates_json(&affine_candidates(text), count)
+}
+
+#[cfg(test)]
+mod test_break_affine {
+    use super::*;
+    use classical::affine_encrypt;
+
+    #[test]
+    fn small() {
+        let plaintext = "The quick brown fox jumps over the lazy dog and runs into the forest";
+        let candidates = affine_candidates(&affine_encrypt(plaintext, 7, 20));
+
+        assert_eq!(candidates.len(), 312);
+        assert_eq!(candidates[0].key, "7,20");
+        assert_eq!(candidates[0].plaintext, plaintext);
+        assert!(break_affine("IHHWVC SWFRCP", 3).starts_with("[{\"key\":"));
+    }
+}
+
+// The shortest period of key, so a key found as LEMONLEMON is reported as LEMON
+fn shortest_period(key: &[i64]) -> &[i64] {
+    let period = (1..key.len() + 1)
+        .find(|&p| key.chunks(p).all(|chunk| chunk == &key[..p]))
+        .unwrap_or(key.len());
+    &key[..period]
+}
+
+// Tries the likeliest key lengths up to max_key_length, from both Kasiski examination and the
+// column index of coincidence. For each, every column is a Caesar cipher broken on its own with
+// chi_squared. Candidates are best first, and the key is its letters.
+// Ref: https://en.wikipedia.org/wiki/Vigen%C3%A8re_cipher#Cryptanalysis
+pub fn vigenere_candidates(text: &str, max_key_length: usize) -> Vec<Candidate> {
+    let letters = key_values(text);
+
+    let mut by_coincidence = vigenere_key_lengths(text, max_key_length);
+    by_coincidence.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+    let mut lengths: Vec<usize> = kasiski_examination(text, max_key_length)
+        .iter()
+        .filter(|&&(_, count)| count > 0)
+        .map(|&(length, _)| length)
+        .take(VIGENERE_LENGTH_GUESSES)
+        .collect();
+    lengths.extend(
+        by_coincidence
+            .iter()
+            .map(|&(length, _)| length)
+            .take(VIGENERE_LENGTH_GUESSES),
+    );
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+    for length in lengths {
+        let key: Vec<i64> = (0..length)
+            .map(|column| {
+                let column: String = letters
+                    .iter()
+                    .skip(column)
+                    .step_by(length)
+                    .map(|&x| (b'A' + x as u8) as char)
+                    .collect();
+                caesar_candidates(&column)[0].key.parse().unwrap()
+            })
+            .collect();
+
+        let key: String = shortest_period(&key)
+            .iter()
+            .map(|&x| (b'A' + letter_mod(x) as u8) as char)
+            .collect();
+        if candidates.iter().any(|c| c.key == key) {
+            continue;
+        }
+
+        let plaintext = vigenere_decrypt(text, &key);
+        candidates.push(Candidate {
+            key,
+            score: chi_squared(&plaintext),
+            plaintext,
+        });
+    }
+
+    ranked(candidates)
+}
+
+#[wasm_bindgen]
+pub fn break_vigenere(text: &str, max_key_length: u32, count: u32) -> String {
+    candidates_json(&vigenere_candidates(text, max_key_length as usize), count)
+}
+
+#[cfg(test)]
+mod test_break_vigenere {
+    use super::*;
+    use classical::vigenere_encrypt;
+
+    #[test]
+    fn medium() {
+        let plaintext = "It was the best of times, it was the worst of times, it was the age of \
+                         wisdom, it was the age of foolishness, it was the epoch of belief, it \
+                         was the epoch of incredulity, it was the season of Light, it was the \
+                         season of Darkness, it was the spring of hope, it was the winter of \
+                         despair, we had everything before us, we had nothing before us.";
+        let ciphertext = vigenere_encrypt(plaintext, "DICKENS");
+
+        let lengths = vigenere_key_lengths(&ciphertext, 10);
+        let best = lengths
+            .iter()
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .unwrap();
+        assert_eq!(best.0 % 7, 0);
+
+        let candidates = vigenere_candidates(&ciphertext, 10);
+        assert_eq!(candidates[0].key, "DICKENS");
+        assert_eq!(candidates[0].plaintext, plaintext);
+    }
+
+    #[test]
+    fn json() {
+        let json = break_vigenere("LXFOPVEFRNHR", 4, 2);
+        assert_eq!(json.matches("\"key\"").count(), 2);
+    }
+
+    #[test]
+    fn huge_max_key_length() {
+        // Only as many lengths as there are letters get tried
+        assert_eq!(
+            vigenere_key_lengths("LXFOPVEFRNHR", 1_000_000_000).len(),
+            12
+        );
+        let json = break_vigenere("LXFOPVEFRNHR", 4294967295, 2);
+        assert_eq!(json.matches("\"key\"").count(), 2);
+    }
+}
+
+// Calls found with every choice of size indices from 0..n in increasing order until it returns
+// Some
+fn first_combination<T, F: FnMut(&[usize]) -> Option<T>>(
+    n: usize,
+    size: usize,
+    chosen: &mut Vec<usize>,
+    found: &mut F,
+) -> Option<T> {
+    if chosen.len() == size {
+        return found(chosen);
+    }
+
+    let start = chosen.last().map(|&i| i + 1).unwrap_or(0);
+    for i in start..n {
+        chosen.push(i);
+        let result = first_combination(n, size, chosen, found);
+        chosen.pop();
+        if result.is_some() {
+            return result;
+        }
+    }
+
+    None
+}
+
+// Known-plaintext attack on the Hill cipher: with the plaintext blocks of some size x size
+// invertible matrix P as columns and the matching ciphertext blocks as C, the key is C P^-1. The key
+// is checked against every block and returned as the letters hill_encrypt takes, or an empty
+// string when the text doesn't contain an invertible set of blocks.
+// Ref: https://en.wikipedia.org/wiki/Hill_cipher#Security
+#[wasm_bindgen]
+pub fn hill_known_plaintext(plaintext: &str, ciphertext: &str, size: u32) -> String {
+    let size = size as usize;
+    let p = key_values(plaintext);
+    let c = key_values(ciphertext);
+    if size == 0 {
+        return String::default();
+    }
+    let blocks = p.len().min(c.len()) / size;
+    if blocks < size {
+        return String::default();
+    }
+
+    let known: String = p[..blocks * size]
+        .iter()
+        .map(|&x| (b'A' + x as u8) as char)
+        .collect();
+    let expected: String = c[..blocks * size]
+        .iter()
+        .map(|&x| (b'A' + x as u8) as char)
+        .collect();
+
+    let key = first_combination(
+        blocks.min(HILL_MAX_BLOCKS),
+        size,
+        &mut Vec::new(),
+        &mut |chosen: &[usize]| {
+            // Column j of P and C is block chosen[j]
+            let column = |values: &[i64], i: usize, j: usize| values[chosen[j] * size + i];
+            let p_matrix: Vec<Vec<i64>> = (0..size)
+                .map(|i| (0..size).map(|j| column(&p, i, j)).collect())
+                .collect();
+            let p_inverse = matrix_inverse_mod_26(&p_matrix)?;
+
+            let key: Vec<Vec<i64>> = (0..size)
+                .map(|i| {
+                    (0..size)
+                        .map(|j| {
+                            letter_mod((0..size).map(|k| column(&c, i, k) * p_inverse[k][j]).sum())
+                        })
+                        .collect()
+                })
+                .collect();
+
+            if matrix_inverse_mod_26(&key).is_some() && hill_apply(&known, &key) == expected {
+                Some(key)
+            } else {
+                None
+            }
+        },
+    );
+
+    match key {
+        Some(key) => key
+            .iter()
+            .flat_map(|row| row.iter().map(|&x| (b'A' + x as u8) as char))
+            .collect(),
+        None => String::default(),
+    }
+}
+
+#[cfg(test)]
+mod test_hill_known_plaintext {
+    use super::*;
+    use classical::hill_encrypt;
+
+    #[test]
+    fn small() {
+        let plaintext = "SHORTEXAMPLE";
+        let ciphertext = hill_encrypt(plaintext, "HILL");
+        assert_eq!(hill_known_plaintext(plaintext, &ciphertext, 2), "HILL");
+    }
+
+    #[test]
+    fn three_by_three() {
+        let plaintext = "Attack the north gate at first light";
+        let ciphertext = hill_encrypt(plaintext, "GYBNQKURP");
+        assert_eq!(hill_known_plaintext(plaintext, &ciphertext, 3), "GYBNQKURP");
+    }
+
+    #[test]
+    fn not_enough_text() {
+        assert_eq!(hill_known_plaintext("ACT", "POH", 3), "");
+        // Every block is the same, so P is never invertible
+        let ciphertext = hill_encrypt("AAAAAA", "HILL");
+        assert_eq!(hill_known_plaintext("AAAAAA", &ciphertext, 2), "");
+    }
+
+    #[test]
+    fn zero_size() {
+        assert_eq!(hill_known_plaintext("SHORTEXAMPLE", "APADJTFTWLFJ", 0), "");
+    }
+}
@@ -0,0 +1,120 @@
+use crypto_math::{number_to_string, string_to_number};
+use num::{bigint::BigInt, Integer, One, Zero};
+
+// Partial quotients [a0; a1, a2, ...] of numerator / denominator for a positive denominator
+pub(crate) fn continued_fraction_num(numerator: &BigInt, denominator: &BigInt) -> Vec<BigInt> {
+    let mut quotients = Vec::new();
+    let mut a = numerator.clone();
+    let mut b = denominator.clone();
+
+    while !b.is_zero() {
+        let (q, r) = a.div_mod_floor(&b);
+        quotients.push(q);
+        a = b;
+        b = r;
+    }
+
+    quotients
+}
+
+// The convergents h_i / k_i from h_i = a_i h_i-1 + h_i-2 and k_i = a_i k_i-1 + k_i-2
+pub(crate) fn convergents_num(numerator: &BigInt, denominator: &BigInt) -> Vec<(BigInt, BigInt)> {
+    let mut h_prev = BigInt::zero();
+    let mut h = BigInt::one();
+    let mut k_prev = BigInt::one();
+    let mut k = BigInt::zero();
+
+    continued_fraction_num(numerator, denominator)
+        .iter()
+        .map(|a| {
+            let h_next = a * &h + &h_prev;
+            let k_next = a * &k + &k_prev;
+
+            h_prev = ::std::mem::replace(&mut h, h_next);
+            k_prev = ::std::mem::replace(&mut k, k_next);
+
+            (h.clone(), k.clone())
+        })
+        .collect()
+}
+
+// Ref: https://en.wikipedia.org/wiki/Continued_fraction
+pub fn continued_fraction(numerator: &str, denominator: &str) -> Vec<String> {
+    continued_fraction_num(&string_to_number(numerator), &string_to_number(denominator))
+        .iter()
+        .map(number_to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod test_continued_fraction {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(continued_fraction("3", "1"), vec!["3"]);
+        assert_eq!(continued_fraction("0", "5"), vec!["0"]);
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(continued_fraction("415", "93"), vec!["4", "2", "6", "7"]);
+        assert_eq!(
+            continued_fraction("17993", "90581"),
+            vec!["0", "5", "29", "4", "1", "3", "2", "4", "3"]
+        );
+    }
+
+    #[test]
+    fn negative() {
+        // Floor division keeps every quotient after the first positive
+        assert_eq!(
+            continued_fraction("-415", "93"),
+            vec!["-5", "1", "1", "6", "7"]
+        );
+    }
+}
+
+// Convergents of numerator / denominator as (numerator, denominator) pairs, each one the best
+// rational approximation with a denominator that small
+pub fn convergents(numerator: &str, denominator: &str) -> Vec<(String, String)> {
+    convergents_num(&string_to_number(numerator), &string_to_number(denominator))
+        .iter()
+        .map(|(h, k)| (number_to_string(h), number_to_string(k)))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_convergents {
+    use super::*;
+
+    fn pairs(xs: &[(&str, &str)]) -> Vec<(String, String)> {
+        xs.iter()
+            .map(|&(h, k)| (h.to_string(), k.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(
+            convergents("415", "93"),
+            pairs(&[("4", "1"), ("9", "2"), ("58", "13"), ("415", "93")])
+        );
+    }
+
+    #[test]
+    fn pi() {
+        // 3.14159265358979 gives the classic 22/7 and 355/113
+        let c = convergents("314159265358979", "100000000000000");
+        assert_eq!(
+            &c[..4],
+            &pairs(&[("3", "1"), ("22", "7"), ("333", "106"), ("355", "113")])[..]
+        );
+    }
+
+    #[test]
+    fn last_is_the_fraction_in_lowest_terms() {
+        let c = convergents("1000", "600");
+        assert_eq!(c.last(), Some(&("5".to_string(), "3".to_string())));
+    }
+}
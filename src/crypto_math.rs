@@ -1,33 +1,31 @@
+use key_recovery::{primes_too_close, wiener_attack_num};
+use montgomery::{MontgomeryContext, OperationCount};
 use num::{
     bigint::{BigInt, RandBigInt, ToBigInt},
-    pow,
+    pow, Integer,
 };
 use num_traits::ToPrimitive;
-use rand::{SeedableRng, StdRng};
+use number_theory::modulo;
+use rand::{Rng, SeedableRng, StdRng};
+use sieve::primes_up_to;
+use std::cell::RefCell;
 use wasm_bindgen::prelude::*;
 
-static SMALL_PRIMES: &'static [i32] = &[
-    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
-    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
-    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
-    311, 313, 317, 331, 337, 347, 349, 353, 359, 367, 373, 379, 383, 389, 397, 401, 409, 419, 421,
-    431, 433, 439, 443, 449, 457, 461, 463, 467, 479, 487, 491, 499, 503, 509, 521, 523, 541, 547,
-    557, 563, 569, 571, 577, 587, 593, 599, 601, 607, 613, 617, 619, 631, 641, 643, 647, 653, 659,
-    661, 673, 677, 683, 691, 701, 709, 719, 727, 733, 739, 743, 751, 757, 761, 769, 773, 787, 797,
-    809, 811, 821, 823, 827, 829, 839, 853, 857, 859, 863, 877, 881, 883, 887, 907, 911, 919, 929,
-    937, 941, 947, 953, 967, 971, 977, 983, 991, 997,
-];
+pub(crate) static BASES: &'static [i32] = &[2, 3, 5, 7, 11];
 
-static BASES: &'static [i32] = &[2, 3, 5, 7, 11];
+// Number of odd candidates generate_prime sieves at once. Prime gaps around 1024 bits average
+// ~710, so a window of 4096 odd numbers (8192 integers) almost always contains a prime.
+const SIEVE_WINDOW: usize = 4096;
 
 // Why lazy_static you may ask? Well, for one, try to compile this without lazy_static. You will
 // get an error saying statics can't be the result of an executed function. So, as per the crate
 // docs, with lazy_static we get Using this macro, it is possible to have statics that require
 // code to be executed at runtime in order to be initialized.
 lazy_static! {
-    static ref ZERO: BigInt = string_to_number("0");
-    static ref ONE: BigInt = string_to_number("1");
-    static ref TWO: BigInt = string_to_number("2");
+    pub(crate) static ref ZERO: BigInt = string_to_number("0");
+    pub(crate) static ref ONE: BigInt = string_to_number("1");
+    pub(crate) static ref TWO: BigInt = string_to_number("2");
+    pub(crate) static ref SMALL_PRIMES: Vec<u64> = primes_up_to(1000);
 }
 
 pub fn string_to_number(s: &str) -> BigInt {
@@ -527,6 +525,11 @@ pub fn miller_rabin(n: &str, seed: &[u8]) -> bool {
 
     let mut rng: StdRng = SeedableRng::from_seed(from_slice(&seed));
 
+    // Every round works mod n, so do the squarings in Montgomery form
+    let context = MontgomeryContext::new(&n_num).unwrap();
+    let one_m = context.to_montgomery(&ONE);
+    let n_minus_one_m = context.to_montgomery(&n_minus_one);
+
     // 50 here is a parameter for accuracy
     for _ in 0..50 {
         let a_num = rng.gen_bigint_range(&*TWO, &n_minus_one);
@@ -539,9 +542,9 @@ pub fn miller_rabin(n: &str, seed: &[u8]) -> bool {
             return false;
         }
 
-        let mut x_num = a_num.modpow(&d, &n_num);
+        let mut x_num = context.to_montgomery(&context.pow(&a_num, &d));
 
-        if x_num == *ONE || x_num == n_minus_one {
+        if x_num == one_m || x_num == n_minus_one_m {
             continue;
         }
 
@@ -549,9 +552,9 @@ pub fn miller_rabin(n: &str, seed: &[u8]) -> bool {
         let mut r = ONE.clone();
 
         while r < s && is_witness {
-            x_num = x_num.modpow(&*TWO, &n_num);
+            x_num = context.mul(&x_num, &x_num);
 
-            if x_num == n_minus_one {
+            if x_num == n_minus_one_m {
                 is_witness = false;
             }
 
@@ -593,8 +596,11 @@ pub fn is_prime(n: &str, seed: &[u8]) -> bool {
 
     let bases_as_bigints: Vec<BigInt> = BASES.iter().map(|x| x.to_bigint().unwrap()).collect();
 
+    // n is odd by now, having survived trial division
+    let context = MontgomeryContext::new(&n_num).unwrap();
+
     for base in &bases_as_bigints {
-        if base.modpow(&n_minus_one, &n_num) != *ONE {
+        if context.pow(base, &n_minus_one) != *ONE {
             return false;
         }
     }
@@ -677,29 +683,192 @@ mod test_is_prime_and_rabin_miller {
     }
 }
 
-pub fn generate_prime(bits: usize, tries: usize, seed: &[u8]) -> Option<String> {
-    let bits_minus_one = bits - 1;
-    let x = pow(TWO.clone(), bits_minus_one);
-    let y = &*TWO * &x;
+// The smallest prime strictly greater than n
+pub fn next_prime(n: &str, seed: &[u8]) -> String {
+    let n_num = string_to_number(n);
 
-    let mut rng: StdRng = SeedableRng::from_seed(from_slice(&seed));
+    if n_num < *TWO {
+        return "2".to_string();
+    }
+
+    let mut candidate = &n_num + &*ONE;
+    if &candidate % &*TWO == *ZERO {
+        candidate += &*ONE;
+    }
+
+    while !is_prime(&number_to_string(&candidate), seed) {
+        candidate += &*TWO;
+    }
+
+    number_to_string(&candidate)
+}
+
+#[cfg(test)]
+mod test_next_prime {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(next_prime("-5", test_seed()), "2");
+        assert_eq!(next_prime("2", test_seed()), "3");
+        assert_eq!(next_prime("3", test_seed()), "5");
+    }
+
+    #[test]
+    fn small() {
+        assert_eq!(next_prime("100", test_seed()), "101");
+        assert_eq!(next_prime("1000", test_seed()), "1009");
+    }
+
+    #[test]
+    fn large() {
+        assert_eq!(next_prime("1000000000000", test_seed()), "1000000000039");
+    }
+
+    #[test]
+    fn x_large() {
+        // The first prime after 2^127 - 1 is 2^127 + 29
+        assert_eq!(
+            next_prime("170141183460469231731687303715884105727", test_seed()),
+            "170141183460469231731687303715884105757"
+        );
+    }
+}
+
+// The largest prime strictly less than n, there is none below 3
+pub fn prev_prime(n: &str, seed: &[u8]) -> Option<String> {
+    let n_num = string_to_number(n);
+
+    if n_num <= *TWO {
+        return None;
+    }
+    if n_num == string_to_number("3") {
+        return Some("2".to_string());
+    }
+
+    let mut candidate = &n_num - &*ONE;
+    if &candidate % &*TWO == *ZERO {
+        candidate -= &*ONE;
+    }
+
+    while !is_prime(&number_to_string(&candidate), seed) {
+        candidate -= &*TWO;
+    }
+
+    Some(number_to_string(&candidate))
+}
+
+#[cfg(test)]
+mod test_prev_prime {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(prev_prime("2", test_seed()), None);
+        assert_eq!(prev_prime("3", test_seed()), Some("2".to_string()));
+        assert_eq!(prev_prime("4", test_seed()), Some("3".to_string()));
+    }
+
+    #[test]
+    fn small() {
+        assert_eq!(prev_prime("100", test_seed()), Some("97".to_string()));
+        assert_eq!(prev_prime("1009", test_seed()), Some("997".to_string()));
+    }
+
+    #[test]
+    fn large() {
+        assert_eq!(
+            prev_prime("1000000000039", test_seed()),
+            Some("999999999989".to_string())
+        );
+    }
+}
+
+// composite[i] marks start + 2i, for odd start, as having a factor in SMALL_PRIMES
+fn sieve_window(start: &BigInt) -> Vec<bool> {
+    let mut composite = vec![false; SIEVE_WINDOW];
+
+    for &prime in SMALL_PRIMES.iter().skip(1) {
+        let p = prime as usize;
+        let r = (start % prime).to_usize().unwrap();
 
-    for _ in 0..tries {
-        let mut n = rng.gen_bigint_range(&x, &y);
+        // Solve start + 2i = 0 (mod p) for i, using p / 2 + 1 as the inverse of 2
+        let mut i = (p - r) % p * (p / 2 + 1) % p;
 
-        if &n % &*TWO == *ZERO {
-            n += 1;
+        // For tiny bit sizes p itself can land in the window. Every odd multiple of p below p^2 has
+        // a smaller prime factor, so crossing out starts at p^2 when that is further along.
+        let square = BigInt::from(p * p);
+        if square > *start {
+            i = ((square - start) / 2u32).to_usize().unwrap();
         }
 
-        let num_str = &number_to_string(&n);
-        let q = is_prime(num_str, seed);
+        while i < SIEVE_WINDOW {
+            composite[i] = true;
+            i += p;
+        }
+    }
+
+    composite
+}
+
+#[cfg(test)]
+mod test_sieve_window {
+    use super::*;
 
-        if q {
-            return Some(number_to_string(&n));
+    // Below 997^2 a number with no factor in SMALL_PRIMES is prime, so the window is exact there
+    fn check(start: u64) {
+        let composite = sieve_window(&BigInt::from(start));
+        for (i, &c) in composite.iter().enumerate() {
+            let candidate = (start + 2 * i as u64).to_string();
+            assert_eq!(c, !is_prime(&candidate, test_seed()), "{}", candidate);
         }
     }
 
-    None
+    #[test]
+    fn miniscule() {
+        check(3);
+    }
+
+    #[test]
+    fn keeps_small_primes() {
+        // 773 is one of SMALL_PRIMES, two past the start
+        assert!(!sieve_window(&BigInt::from(771))[1]);
+        check(771);
+        check(96001);
+    }
+}
+
+// Picks a random odd starting point with the top two bits set (so the product of two such primes
+// has exactly 2 * bits bits), sieves a window of odd candidates with SMALL_PRIMES and only runs
+// is_prime on the survivors. If a window holds no prime we simply draw a new starting point, so
+// this always returns a prime for bits >= 2.
+pub fn generate_prime(bits: usize, seed: &[u8]) -> String {
+    assert!(bits >= 2, "primes need at least 2 bits");
+
+    let lower = pow(TWO.clone(), bits - 1) + pow(TWO.clone(), bits - 2);
+    let upper = pow(TWO.clone(), bits);
+
+    let mut rng: StdRng = SeedableRng::from_seed(from_slice(&seed));
+
+    loop {
+        let mut start = rng.gen_bigint_range(&lower, &upper);
+
+        if &start % &*TWO == *ZERO {
+            start += 1;
+        }
+
+        for (i, _) in sieve_window(&start).iter().enumerate().filter(|&(_, c)| !c) {
+            let candidate = &start + 2 * i;
+
+            if candidate >= upper {
+                break;
+            }
+
+            if is_prime(&number_to_string(&candidate), seed) {
+                return number_to_string(&candidate);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -708,37 +877,60 @@ mod test_generate_prime {
 
     #[test]
     fn miniscule_prime() {
-        let prime = generate_prime(2, 1000, test_seed());
-        assert_eq!(prime, Some("3".to_string()));
+        let prime = generate_prime(2, test_seed());
+        assert_eq!(prime, "3".to_string());
     }
 
     #[test]
     fn tiny_prime() {
-        let prime = generate_prime(8, 1000, test_seed());
-        assert_eq!(prime, Some("193".to_string()));
+        let prime = generate_prime(8, test_seed());
+        assert_eq!(prime, "223".to_string());
     }
 
     #[test]
     fn medium_prime() {
-        let prime = generate_prime(64, 1000, test_seed());
-        assert_eq!(prime, Some("10057321802802702503".to_string()));
+        let prime = generate_prime(64, test_seed());
+        assert_eq!(prime, "16149086009889502499".to_string());
     }
 
     #[test]
     fn large_prime() {
-        let prime = generate_prime(256, 1000, test_seed());
+        let prime = generate_prime(256, test_seed());
         assert_eq!(
             prime,
-            Some(
-                "91585194753718779240055081770127290880143452499556598946529982336565467053363"
-                    .to_string()
-            )
+            "114966634058035650214830057934576985759221289345518872320663318154359625194089"
+                .to_string()
         );
     }
+
+    #[test]
+    fn top_two_bits_set() {
+        for bits in 2..40 {
+            let prime = string_to_number(&generate_prime(bits, test_seed()));
+            let lower = pow(TWO.clone(), bits - 1) + pow(TWO.clone(), bits - 2);
+            let upper = pow(TWO.clone(), bits);
+            assert!(prime >= lower && prime < upper);
+        }
+    }
+
+    #[test]
+    fn product_has_exact_size() {
+        let seed_one = second_test_seed();
+        let p = string_to_number(&generate_prime(128, seed_one));
+        let q = string_to_number(&generate_prime(128, test_seed()));
+        assert_eq!((&p * &q).bits(), 256);
+    }
+
+    #[test]
+    fn x_large_prime() {
+        let prime = generate_prime(1024, test_seed());
+        assert_eq!(string_to_number(&prime).bits(), 1024);
+        assert!(is_prime(&prime, test_seed()));
+    }
 }
 
 // Ref: https://stackoverflow.com/questions/29570607/is-there-a-good-way-to-convert-a-vect-to-an-array
-fn from_slice(bytes: &[u8]) -> [u8; 32] {
+pub(crate) fn from_slice(bytes: &[u8]) -> [u8; 32] {
     let mut array = [0; 32];
     let bytes = &bytes[..array.len()]; // panics if not enough data
     array.copy_from_slice(bytes);
@@ -748,13 +940,25 @@ fn from_slice(bytes: &[u8]) -> [u8; 32] {
 // Fixes: https://github.com/ColbyCypherSociety/ChatDemo/issues/21
 // Ref: https://stackoverflow.com/questions/46378637/how-to-make-a-variable-with-a-scope-lifecycle-for-all-test-functions-in-a-rust-t
 #[allow(dead_code)]
-fn test_seed<'a>() -> &'a[u8] {
+pub(crate) fn test_seed<'a>() -> &'a[u8] {
     &[
         1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
         1, 1,
     ]
 }
 
+// A second seed for tests that need two different ones, like the two primes of a Keypair
+#[allow(dead_code)]
+pub(crate) fn second_test_seed<'a>() -> &'a [u8] {
+    &[
+        10, 16, 51, 42, 123, 31, 212, 31, 233, 15, 9, 7, 41, 32, 4, 3, 144, 122, 1, 35, 1, 13, 55,
+        23, 1, 33, 1, 1, 1, 1, 2, 1,
+    ]
+}
+
+// Largest primes Keypair::new_toy hands out, still small enough to factor n by hand
+const TOY_MAX_PRIME_BITS: usize = 32;
+
 #[wasm_bindgen]
 #[derive(Debug)]
 pub struct Keypair {
@@ -764,18 +968,181 @@ pub struct Keypair {
     d: String,
     // Modulo (both public and private)
     n: String,
+    // The primes and lambda(n) = lcm(p - 1, q - 1), only shown for toy keys
+    p: String,
+    q: String,
+    lambda: String,
+    // Whether the key came from new_toy
+    toy: bool,
+    // Base blinding state for decrypt
+    blinding: RefCell<Blinding>,
+}
+
+// RSA base blinding: decrypt works on c * r^e instead of c and multiplies the result by r^-1, so
+// the value being exponentiated with d is unrelated to the ciphertext an attacker picked. Both
+// factors are squared after every use to get a fresh r without another exponentiation.
+// Ref: Kocher, Timing Attacks on Implementations of Diffie-Hellman, RSA, DSS, and Other Systems
+#[derive(Debug)]
+struct Blinding {
+    // r^e mod n
+    factor: BigInt,
+    // r^-1 mod n
+    inverse: BigInt,
 }
 
 #[wasm_bindgen]
 impl Keypair {
     pub fn new(seed_one: &[u8], seed_two: &[u8]) -> Keypair {
-        // Hardcoded to 256-bits with 1000 tries for now
-        let q_str = generate_prime(256, 1000, &seed_one).unwrap();
-        let q_num = string_to_number(&q_str);
+        Keypair::generate(seed_one, seed_two, 256, None)
+    }
+
+    // Uses the given public exponent instead of a random one, e.g. 3 or 65537. Small exponents
+    // are what hastad_broadcast_attack needs, so this is mostly here for that demonstration.
+    pub fn new_with_exponent(seed_one: &[u8], seed_two: &[u8], e: u32) -> Keypair {
+        assert!(e > 2 && e % 2 == 1, "e must be odd and at least 3");
+        Keypair::generate(seed_one, seed_two, 256, Some(e))
+    }
+
+    // A classroom key with primes of prime_bits bits, between 8 and 32, small enough to work
+    // through by hand. Its private parameters can be read through the getters below.
+    pub fn new_toy(seed_one: &[u8], seed_two: &[u8], prime_bits: u32) -> Keypair {
+        assert!(
+            prime_bits >= 8 && prime_bits as usize <= TOY_MAX_PRIME_BITS,
+            "toy keys need primes of 8 to 32 bits"
+        );
+        Keypair::generate(seed_one, seed_two, prime_bits as usize, None)
+    }
+
+    pub fn public_key_display_wasm(&self) -> String {
+        format!("({}, {})", self.e, self.n)
+    }
+
+    pub fn e(&self) -> String {
+        self.e.clone()
+    }
+
+    pub fn n(&self) -> String {
+        self.n.clone()
+    }
+
+    // The private parameters are only shown for toy keys, and are undefined in JS otherwise
+    pub fn p(&self) -> Option<String> {
+        self.toy_parameter(&self.p)
+    }
+
+    pub fn q(&self) -> Option<String> {
+        self.toy_parameter(&self.q)
+    }
+
+    pub fn p_minus_one(&self) -> Option<String> {
+        Some(number_to_string(&(string_to_number(&self.p()?) - &*ONE)))
+    }
+
+    pub fn q_minus_one(&self) -> Option<String> {
+        Some(number_to_string(&(string_to_number(&self.q()?) - &*ONE)))
+    }
+
+    // Euler's phi(n) = (p - 1)(q - 1), for comparison with lambda
+    pub fn phi(&self) -> Option<String> {
+        Some(number_to_string(
+            &(string_to_number(&self.p_minus_one()?) * string_to_number(&self.q_minus_one()?)),
+        ))
+    }
+
+    // lambda(n) = lcm(p - 1, q - 1), what e and d are inverses modulo
+    pub fn lambda(&self) -> Option<String> {
+        self.toy_parameter(&self.lambda)
+    }
+
+    pub fn d(&self) -> Option<String> {
+        self.toy_parameter(&self.d)
+    }
+
+    // m^e mod n for a single number m < n, instead of encrypt's one value per byte
+    pub fn encrypt_number(&self, m: &str) -> String {
+        let m_num = string_to_number(m);
+        let n_num = string_to_number(&self.n);
+        assert!(
+            m_num >= *ZERO && m_num < n_num,
+            "the message has to be below n"
+        );
+
+        let context = MontgomeryContext::new(&n_num).unwrap();
+        number_to_string(&context.pow(&m_num, &string_to_number(&self.e)))
+    }
+
+    // c^d mod n for a single number, the inverse of encrypt_number
+    pub fn decrypt_number(&self, c: &str) -> String {
+        number_to_string(&self.decrypt_num(&string_to_number(c)))
+    }
+
+    pub fn decrypt(&self, ciphertext: &str) -> String {
+        let private_key = string_to_number(&self.d);
+        let modulus = string_to_number(&self.n);
+
+        // n is a product of two odd primes, set up Montgomery once for the whole message
+        let context = MontgomeryContext::new(&modulus).unwrap();
+
+        let mut decrypted_values: Vec<char> = Vec::new();
+
+        for c in ciphertext.split(',') {
+            let to_decrypt = string_to_number(c);
+            let decrypted = self.private_key_operation(
+                &context,
+                &to_decrypt,
+                &private_key,
+                &mut OperationCount::default(),
+            );
+            let decrypted_u8 = decrypted.to_u8();
+            match decrypted_u8 {
+                Some(d_u8) => decrypted_values.push(d_u8 as char),
+                _ => (),
+            }
+        }
 
-        // Hardcoded to 256-bits with 1000 tries for now
-        let p_str = generate_prime(256, 1000, &seed_two).unwrap();
-        let p_num = string_to_number(&p_str);
+        decrypted_values.iter().collect()
+    }
+}
+
+impl Keypair {
+    // Shared by the constructors: p and q get prime_bits bits each, and a random e is picked when
+    // exponent is None. Keys with at most TOY_MAX_PRIME_BITS bit primes are toy keys.
+    pub(crate) fn generate(
+        seed_one: &[u8],
+        seed_two: &[u8],
+        prime_bits: usize,
+        exponent: Option<u32>,
+    ) -> Keypair {
+        let mut rng: StdRng = SeedableRng::from_seed(from_slice(seed_one));
+
+        let (p_num, q_num) = {
+            let mut q_num = string_to_number(&generate_prime(prime_bits, seed_one));
+            let mut p_num = string_to_number(&generate_prime(prime_bits, seed_two));
+
+            let mut next_prime_from_rng = || {
+                let mut seed = [0u8; 32];
+                rng.fill(&mut seed);
+                string_to_number(&generate_prime(prime_bits, &seed))
+            };
+
+            // A fixed e has to be coprime to lambda, so to both p - 1 and q - 1
+            let usable = |prime: &BigInt| match exponent {
+                Some(e) => (prime - &*ONE).gcd(&e.to_bigint().unwrap()) == *ONE,
+                None => true,
+            };
+
+            while !usable(&q_num) {
+                q_num = next_prime_from_rng();
+            }
+
+            // Independent seeds can still land on primes close enough for fermat_factor, and with
+            // toy sizes even on the same prime
+            while p_num == q_num || primes_too_close(&p_num, &q_num) || !usable(&p_num) {
+                p_num = next_prime_from_rng();
+            }
+
+            (p_num, q_num)
+        };
 
         let n_num = &p_num * &q_num;
         let n_str = number_to_string(&n_num);
@@ -784,58 +1151,108 @@ impl Keypair {
         let q_minus_one_str = number_to_string(&(&q_num - &*ONE));
 
         let phi_str = lcm(&p_minus_one_str, &q_minus_one_str);
+        let toy = prime_bits <= TOY_MAX_PRIME_BITS;
         let phi_num = string_to_number(&phi_str);
 
-        let mut e_found = false;
-
-        let mut rng: StdRng = SeedableRng::from_seed(from_slice(&seed_one));
+        let mut e_found = exponent.is_some();
 
-        let mut e_str = String::default();
+        let mut e_str = exponent.map(|e| e.to_string()).unwrap_or_default();
 
         while !e_found {
             let e_num = rng.gen_bigint_range(&*TWO, &(&phi_num - &*TWO));
 
             e_str = number_to_string(&e_num);
-            if gcd(&e_str, &phi_str) == "1" {
+            // Skip the rare e whose d is small enough for wiener_attack to recover. Toy keys are
+            // broken by factoring anyway, and Wiener's bound covers nearly every d they have.
+            if gcd(&e_str, &phi_str) == "1" && (toy || wiener_attack_num(&e_num, &n_num).is_none())
+            {
                 e_found = true;
             }
         }
 
         let mut d_str = mod_inverse(&e_str, &phi_str).unwrap();
 
-        if &*d_str <  "0" {
-            let d_num = &n_num + string_to_number(&d_str);
+        // mod_inverse can hand back a negative representative, d is only defined modulo lambda
+        if string_to_number(&d_str) < *ZERO {
+            let d_num = &phi_num + string_to_number(&d_str);
             d_str = number_to_string(&d_num);
         }
 
+        let mut r_num = rng.gen_bigint_range(&TWO, &(&n_num - &*ONE));
+        while r_num.gcd(&n_num) != *ONE {
+            r_num = rng.gen_bigint_range(&TWO, &(&n_num - &*ONE));
+        }
+
+        let r_inverse = string_to_number(&mod_inverse(&number_to_string(&r_num), &n_str).unwrap());
+        let blinding = Blinding {
+            factor: r_num.modpow(&string_to_number(&e_str), &n_num),
+            inverse: modulo(&r_inverse, &n_num),
+        };
+
         Keypair {
             e: e_str,
             d: d_str,
             n: n_str,
+            p: number_to_string(&p_num),
+            q: number_to_string(&q_num),
+            lambda: phi_str,
+            toy,
+            blinding: RefCell::new(blinding),
         }
     }
 
-    pub fn public_key_display_wasm(&self) -> String {
-        format!("({}, {})", self.e, self.n)
+    fn toy_parameter(&self, value: &str) -> Option<String> {
+        if self.toy {
+            Some(value.to_string())
+        } else {
+            None
+        }
     }
 
-    pub fn decrypt(&self, ciphertext: &str) -> String {
-        let private_key = string_to_number(&self.d);
-        let modulus = string_to_number(&self.n);
+    // c^d mod n, blinded and with the constant-time exponentiation, for anything touching d. The
+    // exponentiation's Montgomery multiplications are added to count.
+    fn private_key_operation(
+        &self,
+        context: &MontgomeryContext,
+        c: &BigInt,
+        private_key: &BigInt,
+        count: &mut OperationCount,
+    ) -> BigInt {
+        let modulus = context.modulus();
+        let mut blinding = self.blinding.borrow_mut();
 
-        let mut decrypted_values: Vec<char> = Vec::new();
+        let blinded = modulo(c, modulus) * &blinding.factor % modulus;
+        let result = context.pow_fixed_window_counted(&blinded, private_key, modulus.bits(), count);
+        let unblinded = result * &blinding.inverse % modulus;
 
-        for c in ciphertext.split(',') {
-            let to_decrypt = string_to_number(c);
-            let decrypted = to_decrypt.modpow(&private_key, &modulus);
-            let decrypted_u8 = decrypted.to_u8();
-            match decrypted_u8 {
-                Some(d_u8) => decrypted_values.push(d_u8 as char),
-                _ => (),
-            }
-        }
+        blinding.factor = &blinding.factor * &blinding.factor % modulus;
+        blinding.inverse = &blinding.inverse * &blinding.inverse % modulus;
 
-        decrypted_values.iter().collect()
+        unblinded
+    }
+
+    pub(crate) fn public_exponent(&self) -> BigInt {
+        string_to_number(&self.e)
+    }
+
+    pub(crate) fn modulus(&self) -> BigInt {
+        string_to_number(&self.n)
+    }
+
+    pub(crate) fn private_exponent(&self) -> BigInt {
+        string_to_number(&self.d)
+    }
+
+    // c^d mod n for a single number, through the same blinded path as decrypt
+    pub(crate) fn decrypt_num(&self, c: &BigInt) -> BigInt {
+        self.decrypt_num_counted(c, &mut OperationCount::default())
+    }
+
+    // decrypt_num, adding the Montgomery multiplications it does to count
+    pub(crate) fn decrypt_num_counted(&self, c: &BigInt, count: &mut OperationCount) -> BigInt {
+        let modulus = string_to_number(&self.n);
+        let context = MontgomeryContext::new(&modulus).unwrap();
+        self.private_key_operation(&context, c, &string_to_number(&self.d), count)
     }
 }
 
@@ -867,6 +1284,102 @@ mod test_generate_key {
         let decrypted = ciphertext.modpow(&d, &n);
         assert_eq!(plaintext, decrypted);
     }
+
+    #[test]
+    fn blinding_is_refreshed_and_removed() {
+        let seed_one = second_test_seed();
+        let k = Keypair::new(seed_one, test_seed());
+
+        let e = string_to_number(&k.e);
+        let n = string_to_number(&k.n);
+        let context = MontgomeryContext::new(&n).unwrap();
+
+        let plaintext = string_to_number("123456789");
+        let ciphertext = plaintext.modpow(&e, &n);
+        let d = string_to_number(&k.d);
+
+        for _ in 0..3 {
+            let factor_before = k.blinding.borrow().factor.clone();
+            assert_eq!(
+                k.private_key_operation(&context, &ciphertext, &d, &mut OperationCount::default()),
+                plaintext
+            );
+            assert!(k.blinding.borrow().factor != factor_before);
+        }
+
+        // Still r^e and r^-1 for the same r after the squarings
+        let blinding = k.blinding.borrow();
+        assert_eq!(
+            &blinding.factor * blinding.inverse.modpow(&e, &n) % &n,
+            *ONE
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_new_toy {
+    use super::*;
+
+    #[test]
+    fn parameters() {
+        let k = Keypair::new_toy(test_seed(), &[7; 32], 16);
+        let p = string_to_number(&k.p().unwrap());
+        let q = string_to_number(&k.q().unwrap());
+        let lambda = string_to_number(&k.lambda().unwrap());
+
+        assert!(p != q);
+        assert_eq!(p.bits(), 16);
+        assert_eq!(q.bits(), 16);
+        assert_eq!(&p * &q, string_to_number(&k.n()));
+        assert_eq!(
+            k.phi().unwrap(),
+            number_to_string(&((&p - &*ONE) * (&q - &*ONE)))
+        );
+        assert_eq!(
+            k.lambda().unwrap(),
+            lcm(&k.p_minus_one().unwrap(), &k.q_minus_one().unwrap())
+        );
+        assert_eq!(
+            string_to_number(&k.e()) * string_to_number(&k.d().unwrap()) % &lambda,
+            *ONE
+        );
+    }
+
+    #[test]
+    fn single_numbers() {
+        let k = Keypair::new_toy(test_seed(), &[7; 32], 12);
+        for m in &["0", "1", "42", "1000"] {
+            let c = k.encrypt_number(m);
+            assert_eq!(
+                c,
+                number_to_string(
+                    &string_to_number(m)
+                        .modpow(&string_to_number(&k.e()), &string_to_number(&k.n()))
+                )
+            );
+            assert_eq!(k.decrypt_number(&c), *m);
+        }
+    }
+
+    #[test]
+    fn distinct_tiny_primes() {
+        // Only eleven 8-bit primes have the top two bits set, so seeds often pick the same one
+        for i in 0..20u8 {
+            let k = Keypair::new_toy(&[i; 32], &[i + 100; 32], 8);
+            assert!(k.p() != k.q());
+            assert_eq!(k.decrypt_number(&k.encrypt_number("200")), "200");
+        }
+    }
+
+    #[test]
+    fn hidden_for_real_keys() {
+        let k = Keypair::new(test_seed(), &[7; 32]);
+        assert_eq!(k.p(), None);
+        assert_eq!(k.q(), None);
+        assert_eq!(k.phi(), None);
+        assert_eq!(k.lambda(), None);
+        assert_eq!(k.d(), None);
+    }
 }
 
 #[wasm_bindgen]
@@ -874,12 +1387,18 @@ pub fn encrypt(m: &str, e: &str, n: &str) -> String {
     let public_key = string_to_number(e);
     let modulus = string_to_number(n);
 
+    // Anyone can paste in a public key, so only take the Montgomery path for odd moduli
+    let context = MontgomeryContext::new(&modulus);
+
     let mut encrypted_values = String::default();
 
     for c in m.bytes() {
         let c_str = c.to_string();
         let to_encrypt = string_to_number(&c_str);
-        let encrypted = to_encrypt.modpow(&public_key, &modulus);
+        let encrypted = match context {
+            Some(ref context) => context.pow(&to_encrypt, &public_key),
+            None => to_encrypt.modpow(&public_key, &modulus),
+        };
 
         encrypted_values = format!("{},{}", encrypted_values, number_to_string(&encrypted));
     }
@@ -910,4 +1429,18 @@ mod test_encrypt_decrypt {
 
         assert_eq!(plaintext, decrypted);
     }
+
+    #[test]
+    fn negative_inverse_is_moved_into_range() {
+        // mod_inverse returns a negative d for about half of all keys, some of these seeds do
+        for i in 1..5u8 {
+            let k = Keypair::new(&[i; 32], &[i + 100; 32]);
+
+            let plaintext = "HelloWorld!";
+            let ciphertext = encrypt(plaintext, &k.e, &k.n);
+
+            assert!(string_to_number(&k.d) > *ZERO);
+            assert_eq!(k.decrypt(&ciphertext[1..]), plaintext);
+        }
+    }
 }
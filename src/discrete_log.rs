@@ -0,0 +1,371 @@
+// Discrete logarithms, x with g^x = h (mod p). Baby-step giant-step and Pollard's rho both take
+// about sqrt(order) steps, Pohlig–Hellman cuts that down to the square root of the largest prime
+// factor of the order. Each takes a limit on the group operations it may spend, since the
+// interesting inputs are the ones where it runs out. Finding the order of g means factoring p - 1,
+// and the steps Pollard's rho takes there come out of the same limit.
+
+use crypto_math::{mod_inverse, number_to_string, string_to_number, ONE, TWO, ZERO};
+use num::{bigint::BigInt, pow, Integer};
+use num_traits::ToPrimitive;
+use number_theory::{
+    bigint_sqrt, chinese_remainder_num, factorize_within_budget, modulo,
+    multiplicative_order_within_budget,
+};
+use std::collections::HashMap;
+
+// Baby-step giant-step for g^x = h (mod p) with x in [0, order). Every multiplication is charged
+// to budget and the search gives up once it runs out.
+// Ref: https://en.wikipedia.org/wiki/Baby-step_giant-step
+fn baby_step_giant_step_num(
+    g: &BigInt,
+    h: &BigInt,
+    p: &BigInt,
+    order: &BigInt,
+    budget: &mut u64,
+) -> Option<BigInt> {
+    let m = bigint_sqrt(order) + &*ONE;
+    let m_u64 = m.to_u64()?;
+
+    // The baby steps alone need m multiplications, don't start what can't be finished
+    if m_u64 > *budget {
+        return None;
+    }
+
+    let mut baby_steps: HashMap<BigInt, BigInt> = HashMap::new();
+    let mut power = ONE.clone();
+    let mut j = ZERO.clone();
+
+    while j < m {
+        baby_steps.entry(power.clone()).or_insert_with(|| j.clone());
+        power = power * g % p;
+        j += &*ONE;
+    }
+    *budget -= m_u64;
+
+    // g^(-m), g has inverse g^(order - 1)
+    let factor = g.modpow(&(order - &*ONE), p).modpow(&m, p);
+    let mut gamma = modulo(h, p);
+    let mut i = ZERO.clone();
+
+    while i < m {
+        if let Some(j) = baby_steps.get(&gamma) {
+            return Some(modulo(&(&i * &m + j), order));
+        }
+
+        if *budget == 0 {
+            return None;
+        }
+        *budget -= 1;
+
+        gamma = gamma * &factor % p;
+        i += &*ONE;
+    }
+
+    None
+}
+
+// Solves g^x = h (mod p) by baby-step giant-step in about sqrt(ord g) time and space, giving up
+// after limit multiplications. Returns the smallest such x, or None.
+pub fn baby_step_giant_step(g: &str, h: &str, p: &str, limit: u64) -> Option<String> {
+    let g_num = string_to_number(g);
+    let p_num = string_to_number(p);
+
+    let mut budget = limit;
+    let order = multiplicative_order_within_budget(&g_num, &p_num, &mut budget)?;
+    baby_step_giant_step_num(&g_num, &string_to_number(h), &p_num, &order, &mut budget)
+        .map(|x| number_to_string(&x))
+}
+
+#[cfg(test)]
+mod test_baby_step_giant_step {
+    use super::*;
+    use number_theory::find_subgroup_generator;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(
+            baby_step_giant_step("2", "3", "29", 100),
+            Some("5".to_string())
+        );
+        assert_eq!(
+            baby_step_giant_step("3", "1", "7", 100),
+            Some("0".to_string())
+        );
+    }
+
+    #[test]
+    fn no_solution() {
+        // 2 only generates {1, 2, 4} mod 7
+        assert_eq!(baby_step_giant_step("2", "3", "7", 100), None);
+    }
+
+    #[test]
+    fn medium() {
+        let p = "2000303";
+        let g = find_subgroup_generator(p).unwrap();
+        let h = number_to_string(
+            &string_to_number(&g).modpow(&string_to_number("777777"), &string_to_number(p)),
+        );
+        assert_eq!(
+            baby_step_giant_step(&g, &h, p, 10000),
+            Some("777777".to_string())
+        );
+    }
+
+    #[test]
+    fn respects_limit() {
+        let p = "2000303";
+        let g = find_subgroup_generator(p).unwrap();
+        let h = number_to_string(
+            &string_to_number(&g).modpow(&string_to_number("777777"), &string_to_number(p)),
+        );
+        assert_eq!(baby_step_giant_step(&g, &h, p, 100), None);
+    }
+}
+
+// Pollard's rho for logarithms in a group of prime order: walk x = g^a h^b pseudo-randomly until
+// Floyd's cycle finding gives a collision g^a1 h^b1 = g^a2 h^b2, then solve the resulting linear
+// congruence for log h. Different starting points are tried if the collision is useless.
+// Ref: Handbook of Applied Cryptography, Algorithm 3.60
+fn pollard_rho_log_num(
+    g: &BigInt,
+    h: &BigInt,
+    p: &BigInt,
+    order: &BigInt,
+    budget: &mut u64,
+) -> Option<BigInt> {
+    let three = string_to_number("3");
+    let h = modulo(h, p);
+
+    let step = |x: &BigInt, a: &BigInt, b: &BigInt| -> (BigInt, BigInt, BigInt) {
+        match (x % &three).to_u32().unwrap() {
+            0 => (x * x % p, a * &*TWO % order, b * &*TWO % order),
+            1 => (x * g % p, (a + &*ONE) % order, b.clone()),
+            _ => (x * &h % p, a.clone(), (b + &*ONE) % order),
+        }
+    };
+
+    let mut start = ZERO.clone();
+
+    loop {
+        start += &*ONE;
+
+        let mut x = g.modpow(&start, p);
+        let mut a = start.clone();
+        let mut b = ZERO.clone();
+
+        let (mut x2, mut a2, mut b2) = (x.clone(), a.clone(), b.clone());
+
+        loop {
+            if *budget == 0 {
+                return None;
+            }
+            *budget -= 1;
+
+            let next = step(&x, &a, &b);
+            x = next.0;
+            a = next.1;
+            b = next.2;
+
+            let next = step(&x2, &a2, &b2);
+            let next = step(&next.0, &next.1, &next.2);
+            x2 = next.0;
+            a2 = next.1;
+            b2 = next.2;
+
+            if x == x2 {
+                break;
+            }
+        }
+
+        // (b - b2) log h = a2 - a (mod order)
+        let r = modulo(&(&b - &b2), order);
+        let s = modulo(&(&a2 - &a), order);
+        let d = r.gcd(order);
+
+        if r == *ZERO || &s % &d != *ZERO {
+            continue;
+        }
+
+        // There are d candidate solutions, try each one
+        let reduced_order = order / &d;
+        let inverse = mod_inverse(
+            &number_to_string(&(&r / &d)),
+            &number_to_string(&reduced_order),
+        )
+        .unwrap();
+        let x0 = modulo(&(&s / &d * string_to_number(&inverse)), &reduced_order);
+
+        let mut k = ZERO.clone();
+        while k < d {
+            let candidate = &x0 + &k * &reduced_order;
+            if g.modpow(&candidate, p) == h {
+                return Some(candidate);
+            }
+
+            if *budget == 0 {
+                return None;
+            }
+            *budget -= 1;
+
+            k += &*ONE;
+        }
+    }
+}
+
+// Solves g^x = h (mod p) with Pollard's rho in about sqrt(ord g) time but constant space, giving
+// up after limit iterations. Works best when g has prime order, as in a safe-prime subgroup.
+pub fn pollard_rho_log(g: &str, h: &str, p: &str, limit: u64) -> Option<String> {
+    let g_num = string_to_number(g);
+    let p_num = string_to_number(p);
+
+    let mut budget = limit;
+    let order = multiplicative_order_within_budget(&g_num, &p_num, &mut budget)?;
+    pollard_rho_log_num(&g_num, &string_to_number(h), &p_num, &order, &mut budget)
+        .map(|x| number_to_string(&x))
+}
+
+#[cfg(test)]
+mod test_pollard_rho_log {
+    use super::*;
+    use number_theory::find_subgroup_generator;
+
+    #[test]
+    fn tiny() {
+        // 2 has order 11 mod 23
+        let x = pollard_rho_log("2", "9", "23", 1000).unwrap();
+        assert_eq!(x, "5");
+    }
+
+    #[test]
+    fn medium() {
+        let p = "2000303";
+        let g = find_subgroup_generator(p).unwrap();
+        let h = number_to_string(
+            &string_to_number(&g).modpow(&string_to_number("777777"), &string_to_number(p)),
+        );
+        assert_eq!(
+            pollard_rho_log(&g, &h, p, 100000),
+            Some("777777".to_string())
+        );
+    }
+
+    #[test]
+    fn respects_limit() {
+        let p = "2000303";
+        let g = find_subgroup_generator(p).unwrap();
+        let h = number_to_string(
+            &string_to_number(&g).modpow(&string_to_number("777777"), &string_to_number(p)),
+        );
+        assert_eq!(pollard_rho_log(&g, &h, p, 10), None);
+    }
+}
+
+// Pohlig–Hellman: when the order of g factors into small primes the logarithm can be found one
+// prime power at a time (digit by digit with baby-step giant-step in the subgroup of order q) and
+// glued back together with the Chinese remainder theorem. This is why Diffie–Hellman primes must
+// not have a smooth p - 1.
+// Ref: Handbook of Applied Cryptography, Algorithm 3.63
+fn pohlig_hellman_num(g: &BigInt, h: &BigInt, p: &BigInt, budget: &mut u64) -> Option<BigInt> {
+    let order = multiplicative_order_within_budget(g, p, budget)?;
+    let h = modulo(h, p);
+
+    let mut residues = Vec::new();
+    let mut moduli = Vec::new();
+
+    for (q, e) in factorize_within_budget(&order, budget)? {
+        let q_e = pow(q.clone(), e as usize);
+        let cofactor = &order / &q_e;
+
+        // g_i has order q^e and h_i lives in the same subgroup if a solution exists
+        let g_i = g.modpow(&cofactor, p);
+        let h_i = h.modpow(&cofactor, p);
+        let g_i_inverse = g_i.modpow(&(&q_e - &*ONE), p);
+
+        // gamma has order q, each digit of x mod q^e is a logarithm base gamma
+        let gamma = g_i.modpow(&pow(q.clone(), e as usize - 1), p);
+
+        let mut x_i = ZERO.clone();
+        let mut q_k = ONE.clone();
+
+        for k in 0..e {
+            let shifted = g_i_inverse.modpow(&x_i, p) * &h_i % p;
+            let h_k = shifted.modpow(&pow(q.clone(), (e - 1 - k) as usize), p);
+
+            let digit = baby_step_giant_step_num(&gamma, &h_k, p, &q, budget)?;
+            x_i += &digit * &q_k;
+            q_k *= &q;
+        }
+
+        residues.push(x_i);
+        moduli.push(q_e);
+    }
+
+    let (x, _) = chinese_remainder_num(&residues, &moduli)?;
+
+    if g.modpow(&x, p) == h {
+        Some(x)
+    } else {
+        None
+    }
+}
+
+// Solves g^x = h (mod p) with Pohlig–Hellman, which is fast whenever ord g has only small prime
+// factors. limit bounds the total work: factoring the order and baby-step giant-step in all the
+// subproblems.
+pub fn pohlig_hellman(g: &str, h: &str, p: &str, limit: u64) -> Option<String> {
+    let mut budget = limit;
+
+    pohlig_hellman_num(
+        &string_to_number(g),
+        &string_to_number(h),
+        &string_to_number(p),
+        &mut budget,
+    )
+    .map(|x| number_to_string(&x))
+}
+
+#[cfg(test)]
+mod test_pohlig_hellman {
+    use super::*;
+    use number_theory::find_subgroup_generator;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(pohlig_hellman("2", "3", "29", 100), Some("5".to_string()));
+        assert_eq!(pohlig_hellman("2", "3", "7", 100), None);
+    }
+
+    #[test]
+    fn smooth_order() {
+        // p - 1 = 2 * 3 * 41 * 61 * 67^2 * 71 * ... * 197 has no factor above 200
+        let p = "2396900440577371731233176515386182768267";
+        let h = "851000556411380884273913052512713789780";
+        assert_eq!(
+            pohlig_hellman("3", h, p, 10000),
+            Some("123456789123456789123456789".to_string())
+        );
+    }
+
+    #[test]
+    fn safe_prime_resists() {
+        // The same work limit gets nowhere in a safe-prime group of similar size
+        let p = "18446744073709550147";
+        let g = find_subgroup_generator(p).unwrap();
+        let h = number_to_string(
+            &string_to_number(&g).modpow(&string_to_number("123456789123"), &string_to_number(p)),
+        );
+        assert_eq!(pohlig_hellman(&g, &h, p, 10000), None);
+    }
+
+    #[test]
+    fn limit_covers_finding_the_order() {
+        // p - 1 = 2 * 2305843009213706309 * 4611686018427458791, splitting it takes Pollard's rho
+        // about 2^30 steps
+        let p = "21267647932559094848255960642948424839";
+
+        assert_eq!(pohlig_hellman("3", "2", p, 1000), None);
+        assert_eq!(baby_step_giant_step("3", "2", p, 1000), None);
+        assert_eq!(pollard_rho_log("3", "2", p, 1000), None);
+    }
+}
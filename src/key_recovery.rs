@@ -0,0 +1,704 @@
+// Attacks that factor an RSA modulus, or recover the private key, from what a weak key gives
+// away: a leaked d, a d small enough for Wiener's continued fraction attack, primes too close
+// together for Fermat's method, or a prime shared with another key found by batch GCD.
+
+use continued_fraction::convergents_num;
+use crypto_math::{number_to_string, string_to_number, ONE, SMALL_PRIMES, TWO, ZERO};
+use montgomery::MontgomeryContext;
+use num::{
+    bigint::{BigInt, ToBigInt},
+    Integer,
+};
+use num_traits::ToPrimitive;
+use number_theory::bigint_sqrt;
+use wasm_bindgen::prelude::*;
+
+// Largest gcd(p - 1, q - 1) wiener_attack allows for. Keys use lambda(n) instead of phi(n), so
+// the convergent of e / n it is looking for has a denominator of d times a divisor of this gcd.
+const WIENER_MAX_GCD: u32 = 64;
+
+// Recovers p and q from any e, d with e * d = 1 (mod lambda(n)). Writing e * d - 1 = 2^t * r, for
+// most a some a^(r * 2^i) is a square root of 1 other than +-1, which shares a factor with n.
+// Ref: https://en.wikipedia.org/wiki/RSA_(cryptosystem)#Faulty_key_generation
+fn factor_with_private_exponent_num(
+    e: &BigInt,
+    d: &BigInt,
+    n: &BigInt,
+) -> Option<(BigInt, BigInt)> {
+    let k = e * d - &*ONE;
+    if k <= *ZERO || n % &*TWO == *ZERO {
+        return None;
+    }
+
+    let mut r = k.clone();
+    let mut t = 0;
+    while &r % &*TWO == *ZERO {
+        r /= &*TWO;
+        t += 1;
+    }
+
+    let context = MontgomeryContext::new(n)?;
+    let n_minus_one = n - &*ONE;
+
+    for &a in SMALL_PRIMES.iter().take(100) {
+        let mut x = context.pow(&a.to_bigint().unwrap(), &r);
+        if x == *ONE || x == n_minus_one {
+            continue;
+        }
+
+        for _ in 0..t {
+            let y = &x * &x % n;
+
+            if y == *ONE {
+                let p = (&x - &*ONE).gcd(n);
+                let q = n / &p;
+                return Some(if p < q { (p, q) } else { (q, p) });
+            }
+            if y == n_minus_one {
+                break;
+            }
+
+            x = y;
+        }
+    }
+
+    None
+}
+
+pub fn factor_with_private_exponent(e: &str, d: &str, n: &str) -> Option<(String, String)> {
+    factor_with_private_exponent_num(
+        &string_to_number(e),
+        &string_to_number(d),
+        &string_to_number(n),
+    )
+    .map(|(p, q)| (number_to_string(&p), number_to_string(&q)))
+}
+
+#[cfg(test)]
+mod test_factor_with_private_exponent {
+    use super::*;
+    use crypto_math::{second_test_seed, test_seed, Keypair};
+
+    #[test]
+    fn tiny() {
+        assert_eq!(
+            factor_with_private_exponent("17993", "5", "90581"),
+            Some(("239".to_string(), "379".to_string()))
+        );
+    }
+
+    #[test]
+    fn generated_key() {
+        let seed_one = second_test_seed();
+        let k = Keypair::new(seed_one, test_seed());
+        let d = number_to_string(&k.private_exponent());
+        let (p, q) = factor_with_private_exponent(&k.e(), &d, &k.n()).unwrap();
+
+        assert_eq!(
+            string_to_number(&p) * string_to_number(&q),
+            string_to_number(&k.n())
+        );
+        assert!(p != "1" && q != "1");
+    }
+
+    #[test]
+    fn wrong_exponent() {
+        assert_eq!(factor_with_private_exponent("17993", "7", "90581"), None);
+    }
+}
+
+// Wiener's attack: when d < n^(1/4) / 3 the fraction k / d from e * d - k * lambda(n) = 1 is so
+// close to e / n that it shows up among the convergents of e / n. Each candidate d is checked
+// with (2^e)^d = 2 (mod n), which stays cheap because the candidates are small.
+// Ref: https://en.wikipedia.org/wiki/Wiener%27s_attack
+pub(crate) fn wiener_attack_num(e: &BigInt, n: &BigInt) -> Option<(BigInt, BigInt, BigInt)> {
+    let context = MontgomeryContext::new(n)?;
+    let two_to_the_e = context.pow(&TWO, e);
+
+    // Past the Wiener bound (with room for the gcd(p - 1, q - 1) factor) nothing is left to find
+    let bound = bigint_sqrt(&bigint_sqrt(n)) * WIENER_MAX_GCD;
+
+    for (k, denominator) in convergents_num(e, n) {
+        if denominator > bound {
+            break;
+        }
+        if k == *ZERO {
+            continue;
+        }
+
+        for g in 1..WIENER_MAX_GCD + 1 {
+            let g = g.to_bigint().unwrap();
+            if &denominator % &g != *ZERO {
+                continue;
+            }
+
+            let d = &denominator / &g;
+            if d > *ONE && context.pow(&two_to_the_e, &d) == *TWO {
+                if let Some((p, q)) = factor_with_private_exponent_num(e, &d, n) {
+                    return Some((d, p, q));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// Tries to recover (d, p, q) from a public key (e, n) with a small private exponent
+pub fn wiener_attack(e: &str, n: &str) -> Option<(String, String, String)> {
+    wiener_attack_num(&string_to_number(e), &string_to_number(n)).map(|(d, p, q)| {
+        (
+            number_to_string(&d),
+            number_to_string(&p),
+            number_to_string(&q),
+        )
+    })
+}
+
+#[cfg(test)]
+mod test_wiener_attack {
+    use super::*;
+    use crypto_math::{second_test_seed, test_seed, Keypair};
+
+    #[test]
+    fn tiny() {
+        assert_eq!(
+            wiener_attack("17993", "90581"),
+            Some(("5".to_string(), "239".to_string(), "379".to_string()))
+        );
+    }
+
+    #[test]
+    fn small() {
+        // gcd(p - 1, q - 1) = 6 here, so e / n approximates k / (6d) rather than k / d
+        assert_eq!(
+            wiener_attack("33334466669", "1000036000099"),
+            Some((
+                "5".to_string(),
+                "1000003".to_string(),
+                "1000033".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn large() {
+        let e = "943331970787005371874303745952169410058808955129747558792336731292264051745717032220408937444284873758708213619230853440048303045014085760536383000911581";
+        let n = "11296158563155360647326832809554342985409285970312604188681995431505946186326092754412337603900687760758276556398271054776550959684097427238778680536540927";
+        assert_eq!(
+            wiener_attack(e, n),
+            Some((
+                "1267650600228229401496703217737".to_string(),
+                "105299742727819845865226202560177605525449320987102590880178298828398755428309"
+                    .to_string(),
+                "107276221864604353777302540666416096006592077834110215836445435303836364784003"
+                    .to_string(),
+            ))
+        );
+    }
+
+    #[test]
+    fn generated_key_is_safe() {
+        let seed_one = second_test_seed();
+        let k = Keypair::new(seed_one, test_seed());
+        assert_eq!(wiener_attack(&k.e(), &k.n()), None);
+    }
+}
+
+// Whether x mod 64 is a square mod 64, rules out most non-squares before taking a square root
+fn maybe_square(x: &BigInt) -> bool {
+    let low = (x % 64u32).to_u32().unwrap();
+    (0..32u32).any(|r| r * r % 64 == low)
+}
+
+// Fermat's method on target = multiplier * n: walks a up from ceil(sqrt(target)) looking for
+// a^2 - target = b^2, then (a - b)(a + b) = target and gcd(a + b, n) splits n
+fn fermat_search(n: &BigInt, target: &BigInt, iterations: u64) -> Option<(BigInt, BigInt)> {
+    let mut a = bigint_sqrt(target);
+    if &a * &a < *target {
+        a += &*ONE;
+    }
+    let mut r = &a * &a - target;
+
+    for _ in 0..iterations {
+        if maybe_square(&r) {
+            let b = bigint_sqrt(&r);
+            if &b * &b == r {
+                let p = (&a + &b).gcd(n);
+                if p > *ONE && p < *n {
+                    let q = n / &p;
+                    return Some(if p < q { (p, q) } else { (q, p) });
+                }
+            }
+        }
+
+        // (a + 1)^2 - target = a^2 - target + 2a + 1
+        r += &a * &*TWO + &*ONE;
+        a += &*ONE;
+    }
+
+    None
+}
+
+fn fermat_factor_num(n: &BigInt, iterations: u64) -> Option<(BigInt, BigInt)> {
+    if *n <= *TWO {
+        return None;
+    }
+    if n % &*TWO == *ZERO {
+        return Some((TWO.clone(), n / &*TWO));
+    }
+
+    fermat_search(n, n, iterations)
+}
+
+// Fermat's factorization method finds p and q in a handful of steps when they are close: it needs
+// about (q - p)^2 / (8 * sqrt(n)) iterations, so anything within ~n^(1/4) of each other is
+// immediate. Gives up after iterations steps.
+// Ref: https://en.wikipedia.org/wiki/Fermat%27s_factorization_method
+pub fn fermat_factor(n: &str, iterations: u64) -> Option<(String, String)> {
+    fermat_factor_num(&string_to_number(n), iterations)
+        .map(|(p, q)| (number_to_string(&p), number_to_string(&q)))
+}
+
+#[cfg(test)]
+mod test_fermat_factor {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(fermat_factor("2", 10), None);
+        assert_eq!(
+            fermat_factor("10", 10),
+            Some(("2".to_string(), "5".to_string()))
+        );
+        assert_eq!(
+            fermat_factor("49", 10),
+            Some(("7".to_string(), "7".to_string()))
+        );
+    }
+
+    #[test]
+    fn tiny() {
+        // 5959 = 80^2 - 21^2, found on the third step
+        assert_eq!(fermat_factor("5959", 2), None);
+        assert_eq!(
+            fermat_factor("5959", 3),
+            Some(("59".to_string(), "101".to_string()))
+        );
+    }
+
+    #[test]
+    fn prime() {
+        assert_eq!(fermat_factor("1000003", 1000), None);
+    }
+
+    #[test]
+    fn large() {
+        // 256-bit primes which agree in their top ~135 bits
+        let n = "7477796104610631794100703370382368705782452368576777630991281737953932910232396924627265320125416219075454691875320050482303080414099157724857412153701299";
+        assert_eq!(
+            fermat_factor(n, 1),
+            Some((
+                "86474251107544330060372077939409497497320905513732176683371420906429194164757"
+                    .to_string(),
+                "86474251107544330060372077939409497497322234741768765507477851167325939364007"
+                    .to_string(),
+            ))
+        );
+    }
+}
+
+fn lehman_factor_num(n: &BigInt, multipliers: u64, iterations: u64) -> Option<(BigInt, BigInt)> {
+    if *n <= *TWO {
+        return None;
+    }
+    if n % &*TWO == *ZERO {
+        return Some((TWO.clone(), n / &*TWO));
+    }
+
+    (1..multipliers + 1)
+        .filter_map(|k| {
+            let target = n * k.to_bigint().unwrap() * 4u32;
+            fermat_search(n, &target, iterations)
+        })
+        .next()
+}
+
+// Lehman's extension of Fermat's method: running it on 4kn for k = 1, 2, ... also catches primes
+// whose ratio is close to a fraction u / v with uv = k, such as q being almost exactly 3p.
+// Ref: https://en.wikipedia.org/wiki/Lehman%27s_factorization_method
+pub fn lehman_factor(n: &str, multipliers: u64, iterations: u64) -> Option<(String, String)> {
+    lehman_factor_num(&string_to_number(n), multipliers, iterations)
+        .map(|(p, q)| (number_to_string(&p), number_to_string(&q)))
+}
+
+#[cfg(test)]
+mod test_lehman_factor {
+    use super::*;
+
+    #[test]
+    fn tiny() {
+        // With k = 1 the search runs on 4n = 160^2 - 42^2, six steps up from ceil(sqrt(4n))
+        assert_eq!(lehman_factor("5959", 1, 5), None);
+        assert_eq!(
+            lehman_factor("5959", 1, 6),
+            Some(("59".to_string(), "101".to_string()))
+        );
+    }
+
+    #[test]
+    fn large() {
+        // q is 3p plus about 2^90, far apart for plain Fermat but the k = 3 search sees it at once
+        let n = "2868889284880361172411778454076800465393158236615276714870374906096632080629696685411431035569789359265327408074259430679";
+        assert_eq!(fermat_factor(n, 1000), None);
+        assert_eq!(
+            lehman_factor(n, 3, 1),
+            Some((
+                "977904099742635154137825386357724515161593876296343181047109".to_string(),
+                "2933712299227905462413476159073174111044369333180149187266731".to_string(),
+            ))
+        );
+    }
+}
+
+// Fermat steps validate_public_key spends on n, enough to cover |p - q| up to ~2^8 * n^(1/4)
+const FERMAT_VALIDATION_ITERATIONS: u64 = 10000;
+
+// Problems validate_public_key can find from a public key alone
+#[derive(Debug, PartialEq)]
+pub enum KeyWeakness {
+    // d is small enough for wiener_attack to recover
+    SmallPrivateExponent,
+    // p and q are close enough for fermat_factor to split n
+    CloseFactors,
+}
+
+pub fn validate_public_key(e: &str, n: &str) -> Vec<KeyWeakness> {
+    let e_num = string_to_number(e);
+    let n_num = string_to_number(n);
+    let mut weaknesses = Vec::new();
+
+    if wiener_attack_num(&e_num, &n_num).is_some() {
+        weaknesses.push(KeyWeakness::SmallPrivateExponent);
+    }
+    if fermat_factor_num(&n_num, FERMAT_VALIDATION_ITERATIONS).is_some() {
+        weaknesses.push(KeyWeakness::CloseFactors);
+    }
+
+    weaknesses
+}
+
+#[cfg(test)]
+mod test_validate_public_key {
+    use super::*;
+    use crypto_math::{second_test_seed, test_seed, Keypair};
+
+    #[test]
+    fn small_private_exponent() {
+        assert_eq!(
+            validate_public_key("33334466669", "1000036000099"),
+            vec![KeyWeakness::SmallPrivateExponent, KeyWeakness::CloseFactors]
+        );
+    }
+
+    #[test]
+    fn close_factors() {
+        let n = "7477796104610631794100703370382368705782452368576777630991281737953932910232396924627265320125416219075454691875320050482303080414099157724857412153701299";
+        assert_eq!(
+            validate_public_key("65537", n),
+            vec![KeyWeakness::CloseFactors]
+        );
+    }
+
+    #[test]
+    fn generated_key() {
+        let seed_one = second_test_seed();
+        let k = Keypair::new(seed_one, test_seed());
+        assert_eq!(validate_public_key(&k.e(), &k.n()), vec![]);
+    }
+}
+
+// FIPS 186-4 B.3.1 asks for |p - q| > 2^(bits / 2 - 100) with bits the size of n, far beyond what
+// fermat_factor can reach but cheap to check while p and q are still known
+pub(crate) fn primes_too_close(p: &BigInt, q: &BigInt) -> bool {
+    let bits = (p * q).bits();
+    let difference = if p > q { p - q } else { q - p };
+
+    difference.bits() + 100 <= bits / 2
+}
+
+// Levels of the product tree, the moduli at the bottom and their product at the top. An odd one out
+// at the end of a level is carried up unchanged.
+fn product_tree(moduli: &[BigInt]) -> Vec<Vec<BigInt>> {
+    let mut tree = vec![moduli.to_vec()];
+
+    while tree[tree.len() - 1].len() > 1 {
+        let next = tree[tree.len() - 1]
+            .chunks(2)
+            .map(|pair| pair.iter().product())
+            .collect();
+        tree.push(next);
+    }
+
+    tree
+}
+
+// gcd(n_i, product of all the other moduli) for every n_i. Going back down the product tree
+// reduces the full product P mod n_i^2 one level at a time, then (P mod n_i^2) / n_i is the product
+// of the others mod n_i. That is quasi-linear in the number of keys where comparing every pair
+// would be quadratic.
+// Ref: Heninger et al., Mining Your Ps and Qs: Detection of Widespread Weak Keys in Network
+// Devices, USENIX Security 2012
+fn batch_gcd_num(moduli: &[BigInt]) -> Vec<BigInt> {
+    if moduli.is_empty() {
+        return Vec::new();
+    }
+
+    let tree = product_tree(moduli);
+    let mut remainders = tree[tree.len() - 1].clone();
+
+    for level in tree.iter().rev().skip(1) {
+        remainders = level
+            .iter()
+            .enumerate()
+            .map(|(i, node)| &remainders[i / 2] % (node * node))
+            .collect();
+    }
+
+    moduli
+        .iter()
+        .zip(remainders.iter())
+        .map(|(n, r)| (r / n).gcd(n))
+        .collect()
+}
+
+// A modulus has to be an integer above one, zero would wipe out the whole product tree
+fn parse_modulus(n: &str) -> Option<BigInt> {
+    BigInt::parse_bytes(n.trim().as_bytes(), 10).filter(|n| *n > *ONE)
+}
+
+fn parse_moduli(moduli: &[&str]) -> Option<Vec<BigInt>> {
+    moduli.iter().map(|n| parse_modulus(n)).collect()
+}
+
+// For every modulus the gcd with the product of all the others, "1" when it shares no factor with
+// any of them. None when one of the moduli isn't an integer greater than one.
+pub fn batch_gcd(moduli: &[&str]) -> Option<Vec<String>> {
+    let moduli = parse_moduli(moduli)?;
+    Some(
+        batch_gcd_num(&moduli)
+            .iter()
+            .map(number_to_string)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod test_batch_gcd {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(batch_gcd(&[]), Some(vec![]));
+        assert_eq!(batch_gcd(&["15"]), Some(vec!["1".to_string()]));
+    }
+
+    #[test]
+    fn not_moduli() {
+        assert_eq!(batch_gcd(&["0", "15"]), None);
+        assert_eq!(batch_gcd(&["15", "1"]), None);
+        assert_eq!(batch_gcd(&["15", "-33"]), None);
+        assert_eq!(batch_gcd(&["hello"]), None);
+    }
+
+    #[test]
+    fn tiny() {
+        // 3 is shared by the first two, 7 by the last two
+        assert_eq!(
+            batch_gcd(&["15", "33", "91", "119", "667"]).unwrap(),
+            vec!["3", "3", "7", "7", "1"]
+        );
+    }
+
+    #[test]
+    fn both_factors_shared() {
+        // 6 shares 2 with 10 and 3 with 15, so the batch gcd gives back 6 itself
+        assert_eq!(
+            batch_gcd(&["6", "10", "15"]).unwrap(),
+            vec!["6", "10", "15"]
+        );
+        assert_eq!(batch_gcd(&["77", "77"]).unwrap(), vec!["77", "77"]);
+    }
+}
+
+// Every pair of moduli (i, j) with i < j that share a factor, together with gcd(n_i, n_j). That gcd
+// is a prime of both unless the two moduli are identical. batch_gcd finds the keys involved, and
+// only those are compared pairwise. None when one of the moduli isn't an integer greater than one.
+pub fn shared_factors(moduli: &[&str]) -> Option<Vec<(usize, usize, String)>> {
+    let moduli = parse_moduli(moduli)?;
+    let flagged: Vec<usize> = batch_gcd_num(&moduli)
+        .iter()
+        .enumerate()
+        .filter(|&(_, g)| *g != *ONE)
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut pairs = Vec::new();
+    for (k, &i) in flagged.iter().enumerate() {
+        for &j in &flagged[k + 1..] {
+            let g = moduli[i].gcd(&moduli[j]);
+            if g != *ONE {
+                pairs.push((i, j, number_to_string(&g)));
+            }
+        }
+    }
+
+    Some(pairs)
+}
+
+#[cfg(test)]
+mod test_shared_factors {
+    use super::*;
+    use crypto_math::{test_seed, Keypair};
+
+    #[test]
+    fn tiny() {
+        assert_eq!(
+            shared_factors(&["15", "33", "35", "91", "221", "6", "77"]).unwrap(),
+            vec![
+                (0, 1, "3".to_string()),
+                (0, 2, "5".to_string()),
+                (0, 5, "3".to_string()),
+                (1, 5, "3".to_string()),
+                (1, 6, "11".to_string()),
+                (2, 3, "7".to_string()),
+                (2, 6, "7".to_string()),
+                (3, 4, "13".to_string()),
+                (3, 6, "7".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn none_shared() {
+        assert_eq!(shared_factors(&["15", "77", "221"]), Some(vec![]));
+    }
+
+    #[test]
+    fn not_moduli() {
+        assert_eq!(shared_factors(&["15", "0", "33"]), None);
+    }
+
+    #[test]
+    fn colliding_seeds() {
+        // q comes from seed_one alone, so two users who drew the same first seed share it
+        let a = Keypair::new(test_seed(), &[7; 32]);
+        let b = Keypair::new(&[9; 32], &[3; 32]);
+        let c = Keypair::new(test_seed(), &[5; 32]);
+
+        let (a, b, c) = (a.n(), b.n(), c.n());
+
+        let pairs = shared_factors(&[&a, &b, &c]).unwrap();
+        assert_eq!(pairs.len(), 1);
+
+        let (i, j, ref p) = pairs[0];
+        assert_eq!((i, j), (0, 2));
+        let p = string_to_number(p);
+        assert!(p > *ONE && p < string_to_number(&a));
+        assert_eq!(string_to_number(&a) % &p, *ZERO);
+        assert_eq!(string_to_number(&c) % &p, *ZERO);
+    }
+}
+
+// Runs shared_factors over the public keys announced so far, one per line either as shown by
+// public_key_display_wasm or as a bare n, numbered from 0 in the order given. Returns JSON with
+// every pair of keys sharing a prime and how both moduli factor, plus an error entry for every
+// line without a usable modulus. Those lines are left out of the search rather than failing it.
+#[wasm_bindgen]
+pub fn shared_factor_report(public_keys: &str) -> String {
+    let keys: Vec<&str> = public_keys
+        .lines()
+        .map(|key| key.trim().trim_matches(|c| c == '(' || c == ')'))
+        .filter(|key| !key.is_empty())
+        .map(|key| key.rsplit(',').next().unwrap().trim())
+        .collect();
+
+    let (valid, invalid): (Vec<usize>, Vec<usize>) =
+        (0..keys.len()).partition(|&i| parse_modulus(keys[i]).is_some());
+
+    let moduli: Vec<&str> = valid.iter().map(|&i| keys[i]).collect();
+    let pairs: Vec<String> = shared_factors(&moduli)
+        .unwrap()
+        .iter()
+        .map(|&(a, b, ref p)| {
+            let (i, j) = (valid[a], valid[b]);
+            if moduli[a] == moduli[b] {
+                return format!("{{\"keys\":[{},{}],\"same_modulus\":true}}", i, j);
+            }
+
+            let p_num = string_to_number(p);
+            format!(
+                "{{\"keys\":[{},{}],\"same_modulus\":false,\"p\":\"{}\",\"cofactors\":[\"{}\",\"{}\"]}}",
+                i,
+                j,
+                p,
+                string_to_number(moduli[a]) / &p_num,
+                string_to_number(moduli[b]) / &p_num
+            )
+        })
+        .collect();
+
+    let errors: Vec<String> = invalid
+        .iter()
+        .map(|&i| {
+            format!(
+                "{{\"key\":{},\"error\":\"not an integer modulus greater than 1\"}}",
+                i
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\"shared\":[{}],\"errors\":[{}]}}",
+        pairs.join(","),
+        errors.join(",")
+    )
+}
+
+#[cfg(test)]
+mod test_shared_factor_report {
+    use super::*;
+
+    #[test]
+    fn tiny() {
+        assert_eq!(
+            shared_factor_report("(7, 15)\n(5, 77)\n(11, 33)\n"),
+            "{\"shared\":[\
+             {\"keys\":[0,2],\"same_modulus\":false,\"p\":\"3\",\"cofactors\":[\"5\",\"11\"]},\
+             {\"keys\":[1,2],\"same_modulus\":false,\"p\":\"11\",\"cofactors\":[\"7\",\"3\"]}\
+             ],\"errors\":[]}"
+        );
+        assert_eq!(
+            shared_factor_report("221\n(3, 221)"),
+            "{\"shared\":[{\"keys\":[0,1],\"same_modulus\":true}],\"errors\":[]}"
+        );
+        assert_eq!(
+            shared_factor_report("(7, 15)\n(5, 77)"),
+            "{\"shared\":[],\"errors\":[]}"
+        );
+    }
+
+    #[test]
+    fn bad_keys() {
+        assert_eq!(
+            shared_factor_report("hello"),
+            "{\"shared\":[],\"errors\":[{\"key\":0,\"error\":\"not an integer modulus greater than 1\"}]}"
+        );
+
+        // The other keys are still checked, under their original numbers
+        let report = shared_factor_report("(7, 15)\n(3, 0)\n(5, 1)\n(11, 33)");
+        assert!(report.starts_with("{\"shared\":[{\"keys\":[0,3],"));
+        assert!(report.contains("{\"key\":1,"));
+        assert!(report.contains("{\"key\":2,"));
+    }
+}
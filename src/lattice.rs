@@ -0,0 +1,196 @@
+use num::{bigint::BigInt, rational::BigRational, Integer, One, Signed, Zero};
+
+// Dot product of two lattice vectors
+fn dot(a: &[BigInt], b: &[BigInt]) -> BigInt {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+// The nearest integer to a / b for b > 0, rounding halves up
+fn round_div(a: &BigInt, b: &BigInt) -> BigInt {
+    (a * 2u32 + b).div_floor(&(b * 2u32))
+}
+
+// LLL with the usual delta = 3/4
+pub fn lll_reduce(basis: &[Vec<BigInt>]) -> Vec<Vec<BigInt>> {
+    let delta = BigRational::new(BigInt::from(3), BigInt::from(4));
+    lll_reduce_with_delta(basis, &delta)
+}
+
+// LLL reduction of a basis of linearly independent integer vectors. The Gram-Schmidt data is kept
+// as the integers d_i = |b*_1|^2 ... |b*_i|^2 and lambda_ij = d_j mu_ij instead of as fractions,
+// so every step is exact without any rational arithmetic blowing up. delta is the Lovász
+// constant in (1/4, 1), closer to 1 gives shorter vectors for more swaps.
+// Ref: Cohen, A Course in Computational Algebraic Number Theory, Algorithm 2.6.7
+pub fn lll_reduce_with_delta(basis: &[Vec<BigInt>], delta: &BigRational) -> Vec<Vec<BigInt>> {
+    assert!(
+        *delta > BigRational::new(BigInt::one(), BigInt::from(4)) && *delta < BigRational::one(),
+        "delta has to be in (1/4, 1)"
+    );
+
+    let rows = basis.len();
+    let mut b: Vec<Vec<BigInt>> = basis.to_vec();
+    if rows < 2 {
+        return b;
+    }
+
+    // d[0] = 1 and d[i + 1] is the Gram determinant of the first i + 1 vectors
+    let mut d = vec![BigInt::zero(); rows + 1];
+    let mut lambda = vec![vec![BigInt::zero(); rows]; rows];
+    d[0] = BigInt::one();
+    d[1] = dot(&b[0], &b[0]);
+
+    let mut k = 1;
+    let mut k_max = 0;
+
+    while k < rows {
+        // Incremental Gram-Schmidt for a vector not seen before
+        if k > k_max {
+            k_max = k;
+            for j in 0..k + 1 {
+                let mut u = dot(&b[k], &b[j]);
+                for i in 0..j {
+                    u = (&d[i + 1] * u - &lambda[k][i] * &lambda[j][i]) / &d[i];
+                }
+                if j < k {
+                    lambda[k][j] = u;
+                } else {
+                    assert!(!u.is_zero(), "the basis vectors are linearly dependent");
+                    d[k + 1] = u;
+                }
+            }
+        }
+
+        reduce(&mut b, &mut lambda, &d, k, k - 1);
+
+        // Lovász condition: d_k d_(k-2) >= delta d_(k-1)^2 - lambda^2, scaled by delta's denominator
+        let left = delta.denom() * &d[k + 1] * &d[k - 1];
+        let right =
+            delta.numer() * &d[k] * &d[k] - delta.denom() * &lambda[k][k - 1] * &lambda[k][k - 1];
+
+        if left < right {
+            swap(&mut b, &mut lambda, &mut d, k, k_max);
+            if k > 1 {
+                k -= 1;
+            }
+        } else {
+            for l in (0..k - 1).rev() {
+                reduce(&mut b, &mut lambda, &d, k, l);
+            }
+            k += 1;
+        }
+    }
+
+    b
+}
+
+// Size reduction of b_k against b_l so that |mu_kl| <= 1/2
+fn reduce(b: &mut [Vec<BigInt>], lambda: &mut [Vec<BigInt>], d: &[BigInt], k: usize, l: usize) {
+    if lambda[k][l].abs() * 2u32 <= d[l + 1] {
+        return;
+    }
+
+    let q = round_div(&lambda[k][l], &d[l + 1]);
+
+    let reduced: Vec<BigInt> = b[k]
+        .iter()
+        .zip(b[l].iter())
+        .map(|(x, y)| x - &q * y)
+        .collect();
+    b[k] = reduced;
+
+    lambda[k][l] = &lambda[k][l] - &q * &d[l + 1];
+
+    let (lower, upper) = lambda.split_at_mut(k);
+    for (x, y) in upper[0].iter_mut().zip(lower[l].iter()).take(l) {
+        *x -= &q * y;
+    }
+}
+
+// Exchanges b_k and b_(k-1) and updates the Gram-Schmidt data to match
+fn swap(
+    b: &mut [Vec<BigInt>],
+    lambda: &mut [Vec<BigInt>],
+    d: &mut [BigInt],
+    k: usize,
+    k_max: usize,
+) {
+    b.swap(k, k - 1);
+    {
+        let (lower, upper) = lambda.split_at_mut(k);
+        lower[k - 1][..k - 1].swap_with_slice(&mut upper[0][..k - 1]);
+    }
+
+    let l = lambda[k][k - 1].clone();
+    let new_d = (&d[k - 1] * &d[k + 1] + &l * &l) / &d[k];
+
+    for row in lambda.iter_mut().take(k_max + 1).skip(k + 1) {
+        let t = row[k].clone();
+        row[k] = (&d[k + 1] * &row[k - 1] - &l * &t) / &d[k];
+        row[k - 1] = (&new_d * t + &l * &row[k]) / &d[k + 1];
+    }
+
+    d[k] = new_d;
+}
+
+#[cfg(test)]
+mod test_lll_reduce {
+    use super::*;
+
+    fn lattice(rows: &[&[i64]]) -> Vec<Vec<BigInt>> {
+        rows.iter()
+            .map(|row| row.iter().map(|&x| BigInt::from(x)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn already_reduced() {
+        let basis = lattice(&[&[1, 0], &[0, 1]]);
+        assert_eq!(lll_reduce(&basis), basis);
+    }
+
+    #[test]
+    fn small() {
+        // The textbook example, reducing to (0, 1, 0), (1, 0, 1), (-1, 0, 2)
+        let basis = lattice(&[&[1, 1, 1], &[-1, 0, 2], &[3, 5, 6]]);
+        assert_eq!(
+            lll_reduce(&basis),
+            lattice(&[&[0, 1, 0], &[1, 0, 1], &[-1, 0, 2]])
+        );
+    }
+
+    #[test]
+    fn integer_relation() {
+        // With the big weight K on the last column short vectors need x a + y b small, so LLL turns
+        // up the relation y a - x b = 0 and then x a + y b = gcd(x, y) = 9
+        let x = BigInt::from(123456789);
+        let y = BigInt::from(987654321);
+        let k = BigInt::from(1u64 << 40);
+        let basis = vec![
+            vec![BigInt::one(), BigInt::zero(), &k * &x],
+            vec![BigInt::zero(), BigInt::one(), &k * &y],
+        ];
+        let reduced = lll_reduce(&basis);
+
+        assert_eq!(reduced[0][2], BigInt::zero());
+        assert_eq!(&reduced[0][0] * &x + &reduced[0][1] * &y, BigInt::zero());
+        assert_eq!(reduced[0][0].abs(), BigInt::from(109739369));
+        assert_eq!(reduced[1][2].abs(), &k * 9);
+    }
+
+    #[test]
+    fn stricter_delta() {
+        let basis = lattice(&[
+            &[105, 821, 404, 328],
+            &[881, 667, 644, 927],
+            &[181, 483, 87, 500],
+            &[893, 834, 732, 441],
+        ]);
+        let loose = lll_reduce(&basis);
+        let strict = lll_reduce_with_delta(
+            &basis,
+            &BigRational::new(BigInt::from(99), BigInt::from(100)),
+        );
+
+        assert!(dot(&strict[0], &strict[0]) <= dot(&loose[0], &loose[0]));
+    }
+}
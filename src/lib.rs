@@ -6,4 +6,22 @@ extern crate wasm_bindgen;
 #[macro_use]
 extern crate lazy_static;
 
+pub mod bleichenbacher;
+pub mod classical;
+pub mod classical_analysis;
+pub mod continued_fraction;
 pub mod crypto_math;
+pub mod discrete_log;
+pub mod key_recovery;
+pub mod lattice;
+pub mod malleability;
+pub mod matrix;
+pub mod message_recovery;
+pub mod montgomery;
+pub mod number_theory;
+pub mod polynomial;
+pub mod pseudoprime;
+pub mod sieve;
+pub mod special_primes;
+pub mod timing;
+pub mod trace;
@@ -0,0 +1,233 @@
+// Textbook RSA is multiplicatively homomorphic: (m1^e)(m2^e) = (m1 m2)^e mod n, so anyone holding
+// ciphertexts can combine them into the encryption of a related message without knowing d. The
+// same property is what lets Keypair blind its private-key operation, and what lets an attacker
+// walk a decryption oracle that refuses one ciphertext into decrypting it anyway.
+// Ref: https://en.wikipedia.org/wiki/Malleability_(cryptography)
+
+use crypto_math::{mod_inverse, number_to_string, string_to_number, Keypair};
+use num::{bigint::BigInt, Integer};
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+// The encryption of m1 m2 mod n from the encryptions of m1 and m2
+#[wasm_bindgen]
+pub fn multiply_ciphertexts(c1: &str, c2: &str, n: &str) -> String {
+    let n_num = string_to_number(n);
+    let product = string_to_number(c1) * string_to_number(c2);
+
+    number_to_string(&product.mod_floor(&n_num))
+}
+
+#[cfg(test)]
+mod test_multiply_ciphertexts {
+    use super::*;
+
+    #[test]
+    fn tiny() {
+        // n = 3233, e = 17: 6^17 = 824 and 7^17 = 2369, 42^17 = 2557
+        assert_eq!(multiply_ciphertexts("824", "2369", "3233"), "2557");
+    }
+}
+
+// Multiplies every value in a ciphertext in encrypt's format by factor^e, so each byte decrypts
+// to factor times what was sent. Products past 255 are dropped by Keypair::decrypt, which is
+// enough to make a message say something else without touching the key.
+#[wasm_bindgen]
+pub fn scale_ciphertext(ciphertext: &str, factor: &str, e: &str, n: &str) -> String {
+    let n_num = string_to_number(n);
+    let multiplier = string_to_number(factor).modpow(&string_to_number(e), &n_num);
+
+    ciphertext
+        .split(',')
+        .filter(|c| !c.is_empty())
+        .map(|c| format!(",{}", (string_to_number(c) * &multiplier).mod_floor(&n_num)))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_scale_ciphertext {
+    use super::*;
+    use crypto_math::{encrypt, test_seed};
+
+    #[test]
+    fn doubles_every_byte() {
+        let k = Keypair::new(test_seed(), &[7; 32]);
+        let (e, n) = (
+            number_to_string(&k.public_exponent()),
+            number_to_string(&k.modulus()),
+        );
+
+        // '0' + '0' = '`', '1' + '1' = 'b' and so on
+        let tampered = scale_ciphertext(&encrypt("0123", &e, &n), "2", &e, &n);
+        assert_eq!(k.decrypt(&tampered[1..]), "`bdf");
+    }
+}
+
+// c r^e mod n: decrypts to m r, which says nothing about m to whoever decrypts it
+#[wasm_bindgen]
+pub fn blind(c: &str, r: &str, e: &str, n: &str) -> String {
+    let n_num = string_to_number(n);
+    let factor = string_to_number(r).modpow(&string_to_number(e), &n_num);
+
+    number_to_string(&(string_to_number(c) * factor).mod_floor(&n_num))
+}
+
+// Recovers m from the decryption m r of a blinded ciphertext. Returns an empty string when r has
+// no inverse mod n, which only happens if r shares a factor with n.
+#[wasm_bindgen]
+pub fn unblind(blinded_message: &str, r: &str, n: &str) -> String {
+    let n_num = string_to_number(n);
+
+    match mod_inverse(r, n) {
+        Some(inverse) => {
+            let m = string_to_number(blinded_message) * string_to_number(&inverse);
+            number_to_string(&m.mod_floor(&n_num))
+        }
+        None => String::default(),
+    }
+}
+
+#[cfg(test)]
+mod test_blind_unblind {
+    use super::*;
+
+    #[test]
+    fn tiny() {
+        // n = 3233, e = 17, d = 413: 65^17 = 2790
+        let blinded = blind("2790", "5", "17", "3233");
+        assert_eq!(blinded, "461");
+
+        let m = string_to_number(&blinded).modpow(&BigInt::from(413), &BigInt::from(3233));
+        assert_eq!(number_to_string(&m), "325");
+        assert_eq!(unblind("325", "5", "3233"), "65");
+    }
+
+    #[test]
+    fn not_invertible() {
+        assert_eq!(unblind("325", "61", "3233"), "");
+    }
+}
+
+// A server that decrypts any single-number ciphertext for anyone, except the ones it has been told
+// to protect. Every decryption it performs is kept so the demo can show what it was asked.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct DecryptionOracle {
+    keypair: Keypair,
+    protected: RefCell<Vec<BigInt>>,
+    // Ciphertexts decrypted so far, in order
+    log: RefCell<Vec<BigInt>>,
+}
+
+#[wasm_bindgen]
+impl DecryptionOracle {
+    pub fn new(seed_one: &[u8], seed_two: &[u8]) -> DecryptionOracle {
+        DecryptionOracle {
+            keypair: Keypair::new(seed_one, seed_two),
+            protected: RefCell::new(Vec::new()),
+            log: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn public_key_display_wasm(&self) -> String {
+        format!(
+            "({}, {})",
+            self.keypair.public_exponent(),
+            self.keypair.modulus()
+        )
+    }
+
+    // Encrypts message, a number below n, and refuses to ever decrypt the result
+    pub fn encrypt_protected(&self, message: &str) -> String {
+        let c = string_to_number(message)
+            .modpow(&self.keypair.public_exponent(), &self.keypair.modulus());
+        self.protected.borrow_mut().push(c.clone());
+
+        number_to_string(&c)
+    }
+
+    // The plaintext of c, or an empty string for a protected ciphertext
+    pub fn decrypt(&self, c: &str) -> String {
+        let c_num = string_to_number(c).mod_floor(&self.keypair.modulus());
+        if self.protected.borrow().contains(&c_num) {
+            return String::default();
+        }
+
+        self.log.borrow_mut().push(c_num.clone());
+        number_to_string(&self.keypair.decrypt_num(&c_num))
+    }
+
+    pub fn queries(&self) -> u32 {
+        self.log.borrow().len() as u32
+    }
+}
+
+// The scripted chosen-ciphertext attack: the oracle won't decrypt c, but it will decrypt the
+// blinded c r^e, and dividing that answer by r gives the protected message. Returns the steps one
+// per line for the demo to show, ending in the recovered message.
+#[wasm_bindgen]
+pub fn chosen_ciphertext_attack(oracle: &DecryptionOracle, c: &str, r: &str) -> String {
+    let e = number_to_string(&oracle.keypair.public_exponent());
+    let n = number_to_string(&oracle.keypair.modulus());
+
+    let mut steps = Vec::new();
+
+    let refused = oracle.decrypt(c);
+    if refused.is_empty() {
+        steps.push(format!("Oracle refuses to decrypt c = {}", c));
+    } else {
+        steps.push(format!("Oracle decrypts c = {} directly to {}", c, refused));
+        return steps.join("\n");
+    }
+
+    let blinded = blind(c, r, &e, &n);
+    steps.push(format!("Blinded c' = c * {}^e mod n = {}", r, blinded));
+
+    let answer = oracle.decrypt(&blinded);
+    if answer.is_empty() {
+        steps.push("Oracle refuses c' too, try another r".to_string());
+        return steps.join("\n");
+    }
+    steps.push(format!("Oracle decrypts c' to m * r = {}", answer));
+
+    let m = unblind(&answer, r, &n);
+    if m.is_empty() {
+        steps.push(format!("{} has no inverse mod n, try another r", r));
+    } else {
+        steps.push(format!("m = {} * {}^-1 mod n = {}", answer, r, m));
+    }
+
+    steps.join("\n")
+}
+
+#[cfg(test)]
+mod test_chosen_ciphertext_attack {
+    use super::*;
+    use crypto_math::test_seed;
+
+    #[test]
+    fn recovers_protected_message() {
+        let oracle = DecryptionOracle::new(test_seed(), &[7; 32]);
+        let c = oracle.encrypt_protected("1234567890");
+
+        assert_eq!(oracle.decrypt(&c), "");
+
+        let steps = chosen_ciphertext_attack(&oracle, &c, "31337");
+        assert!(steps.starts_with("Oracle refuses to decrypt c = "));
+        assert!(steps.ends_with("mod n = 1234567890"));
+        // The only thing it decrypted was the blinded ciphertext
+        assert_eq!(oracle.queries(), 1);
+        assert!(oracle.log.borrow()[0] != string_to_number(&c));
+    }
+
+    #[test]
+    fn unprotected() {
+        let oracle = DecryptionOracle::new(test_seed(), &[7; 32]);
+        let e = oracle.keypair.public_exponent();
+        let n = oracle.keypair.modulus();
+        let c = number_to_string(&BigInt::from(42).modpow(&e, &n));
+
+        assert_eq!(oracle.decrypt(&c), "42");
+        assert!(chosen_ciphertext_attack(&oracle, &c, "2").ends_with("directly to 42"));
+    }
+}
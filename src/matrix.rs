@@ -0,0 +1,602 @@
+use crypto_math::{extended_gcd, mod_inverse, number_to_string, string_to_number};
+use num::{bigint::BigInt, Integer, One, Signed, Zero};
+
+// Matrices with entries in Z/nZ. n can be composite: elimination then works with gcd steps instead
+// of dividing by the pivot, so determinants, echelon forms and solving don't need any inverses,
+// and only inverse and kernel ask for invertible pivots. With n = 2 this is linear algebra over
+// GF(2), as in the last stage of the quadratic sieve.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModMatrix {
+    // Row by row, every entry in [0, n)
+    rows: Vec<Vec<BigInt>>,
+    modulus: BigInt,
+}
+
+// a^-1 mod n as a BigInt in [0, n)
+fn inverse(a: &BigInt, n: &BigInt) -> Option<BigInt> {
+    mod_inverse(&number_to_string(a), &number_to_string(n))
+        .map(|u| string_to_number(&u).mod_floor(n))
+}
+
+// (s, t, u, v) with det 1 taking (a, b) to (gcd(a, b), 0) as s a + t b and u a + v b, for a != 0.
+// Applying it to two rows (or columns) is the elimination step that works without division.
+fn elimination_step(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt, BigInt) {
+    if b.is_multiple_of(a) {
+        return (BigInt::one(), BigInt::zero(), -(b / a), BigInt::one());
+    }
+
+    let (x, y) = extended_gcd(&number_to_string(a), &number_to_string(b));
+    let (x, y) = (string_to_number(&x), string_to_number(&y));
+    let g = a * &x + b * &y;
+
+    (x, y, -(b / &g), a / &g)
+}
+
+// rows[i], rows[j] = s rows[i] + t rows[j], u rows[i] + v rows[j]
+fn mix_rows(
+    rows: &mut [Vec<BigInt>],
+    i: usize,
+    j: usize,
+    step: &(BigInt, BigInt, BigInt, BigInt),
+    n: &BigInt,
+) {
+    let (ref s, ref t, ref u, ref v) = *step;
+    for column in 0..rows[i].len() {
+        let (a, b) = (rows[i][column].clone(), rows[j][column].clone());
+        rows[i][column] = (s * &a + t * &b).mod_floor(n);
+        rows[j][column] = (u * &a + v * &b).mod_floor(n);
+    }
+}
+
+// The same step on columns i and j
+fn mix_columns(
+    rows: &mut [Vec<BigInt>],
+    i: usize,
+    j: usize,
+    step: &(BigInt, BigInt, BigInt, BigInt),
+    n: &BigInt,
+) {
+    let (ref s, ref t, ref u, ref v) = *step;
+    for row in rows.iter_mut() {
+        let (a, b) = (row[i].clone(), row[j].clone());
+        row[i] = (s * &a + t * &b).mod_floor(n);
+        row[j] = (u * &a + v * &b).mod_floor(n);
+    }
+}
+
+fn swap_columns(rows: &mut [Vec<BigInt>], i: usize, j: usize) {
+    for row in rows.iter_mut() {
+        row.swap(i, j);
+    }
+}
+
+impl ModMatrix {
+    // Panics unless every row has the same length and the modulus is positive
+    pub fn new(rows: &[Vec<BigInt>], modulus: &BigInt) -> ModMatrix {
+        assert!(modulus.is_positive(), "the modulus needs to be positive");
+        assert!(
+            rows.iter().all(|row| row.len() == rows[0].len()),
+            "all rows need the same length"
+        );
+
+        ModMatrix {
+            rows: rows
+                .iter()
+                .map(|row| row.iter().map(|x| x.mod_floor(modulus)).collect())
+                .collect(),
+            modulus: modulus.clone(),
+        }
+    }
+
+    pub fn zero(row_count: usize, column_count: usize, modulus: &BigInt) -> ModMatrix {
+        ModMatrix::new(
+            &vec![vec![BigInt::zero(); column_count]; row_count],
+            modulus,
+        )
+    }
+
+    pub fn identity(size: usize, modulus: &BigInt) -> ModMatrix {
+        let mut identity = ModMatrix::zero(size, size, modulus);
+        for i in 0..size {
+            identity.rows[i][i] = BigInt::one().mod_floor(modulus);
+        }
+        identity
+    }
+
+    pub fn rows(&self) -> &[Vec<BigInt>] {
+        &self.rows
+    }
+
+    pub fn modulus(&self) -> &BigInt {
+        &self.modulus
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.rows.first().map(|row| row.len()).unwrap_or(0)
+    }
+
+    pub fn transpose(&self) -> ModMatrix {
+        let transposed: Vec<Vec<BigInt>> = (0..self.column_count())
+            .map(|j| self.rows.iter().map(|row| row[j].clone()).collect())
+            .collect();
+        ModMatrix::new(&transposed, &self.modulus)
+    }
+
+    pub fn add(&self, other: &ModMatrix) -> ModMatrix {
+        self.combine(other, |a, b| a + b)
+    }
+
+    pub fn sub(&self, other: &ModMatrix) -> ModMatrix {
+        self.combine(other, |a, b| a - b)
+    }
+
+    pub fn scale(&self, c: &BigInt) -> ModMatrix {
+        let scaled: Vec<Vec<BigInt>> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|x| x * c).collect())
+            .collect();
+        ModMatrix::new(&scaled, &self.modulus)
+    }
+
+    pub fn mul(&self, other: &ModMatrix) -> ModMatrix {
+        assert_eq!(
+            self.column_count(),
+            other.row_count(),
+            "the matrices don't fit together"
+        );
+
+        let product: Vec<Vec<BigInt>> = self
+            .rows
+            .iter()
+            .map(|row| {
+                (0..other.column_count())
+                    .map(|j| {
+                        row.iter()
+                            .zip(other.rows.iter())
+                            .map(|(a, other_row)| a * &other_row[j])
+                            .sum()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        ModMatrix::new(&product, &self.modulus)
+    }
+
+    // The matrix times a column vector
+    pub fn mul_vector(&self, x: &[BigInt]) -> Vec<BigInt> {
+        assert_eq!(self.column_count(), x.len(), "the vector doesn't fit");
+
+        self.rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(x.iter())
+                    .map(|(a, b)| a * b)
+                    .sum::<BigInt>()
+                    .mod_floor(&self.modulus)
+            })
+            .collect()
+    }
+
+    // Square and multiply, for square matrices
+    pub fn pow(&self, mut exponent: u32) -> ModMatrix {
+        let mut result = ModMatrix::identity(self.row_count(), &self.modulus);
+        let mut base = self.clone();
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.mul(&base);
+            }
+            base = base.mul(&base);
+            exponent >>= 1;
+        }
+
+        result
+    }
+
+    // Gaussian elimination to row echelon form, with the columns that got a pivot. Every step has
+    // determinant 1 or is a swap, so over a composite n pivots are gcds rather than ones.
+    // Ref: https://en.wikipedia.org/wiki/Gaussian_elimination
+    pub fn row_echelon(&self) -> (ModMatrix, Vec<usize>) {
+        let (echelon, pivots, _) = self.eliminate();
+        (echelon, pivots)
+    }
+
+    // Product of the diagonal after elimination, with the sign of the row swaps
+    pub fn determinant(&self) -> BigInt {
+        assert_eq!(
+            self.row_count(),
+            self.column_count(),
+            "only square matrices have a determinant"
+        );
+
+        let (echelon, pivots, swaps) = self.eliminate();
+        if pivots.len() < self.row_count() {
+            return BigInt::zero();
+        }
+
+        let product = (0..self.row_count()).fold(BigInt::one(), |acc, i| {
+            acc * &echelon.rows[i][i] % &self.modulus
+        });
+        if swaps % 2 == 0 {
+            product.mod_floor(&self.modulus)
+        } else {
+            (-product).mod_floor(&self.modulus)
+        }
+    }
+
+    // Gauss-Jordan on [A | I], None unless the determinant is invertible mod n
+    pub fn inverse(&self) -> Option<ModMatrix> {
+        let size = self.row_count();
+        assert_eq!(
+            size,
+            self.column_count(),
+            "only square matrices have inverses"
+        );
+        // The empty product, matching a determinant of 1
+        if size == 0 {
+            return Some(self.clone());
+        }
+
+        let identity = ModMatrix::identity(size, &self.modulus);
+        let augmented: Vec<Vec<BigInt>> = self
+            .rows
+            .iter()
+            .zip(identity.rows.iter())
+            .map(|(row, unit)| row.iter().chain(unit.iter()).cloned().collect())
+            .collect();
+
+        let (reduced, pivots) = ModMatrix::new(&augmented, &self.modulus).reduced_row_echelon()?;
+        if pivots.len() < size || pivots[size - 1] >= size {
+            return None;
+        }
+
+        let inverse: Vec<Vec<BigInt>> = reduced
+            .rows
+            .iter()
+            .map(|row| row[size..].to_vec())
+            .collect();
+        Some(ModMatrix::new(&inverse, &self.modulus))
+    }
+
+    // One x with A x = b mod n, None if there is none. Works for composite n through the Smith
+    // normal form D = U A V: D y = U b splits into single linear congruences and x = V y.
+    // Ref: https://en.wikipedia.org/wiki/Smith_normal_form#Applications
+    pub fn solve(&self, b: &[BigInt]) -> Option<Vec<BigInt>> {
+        assert_eq!(self.row_count(), b.len(), "b needs one entry per row");
+        let n = &self.modulus;
+
+        let (u, d, v) = self.smith();
+        let target = u.mul_vector(b);
+
+        let mut y = vec![BigInt::zero(); self.column_count()];
+        for (i, r) in target.iter().enumerate() {
+            let diagonal = if i < self.column_count() {
+                d.rows[i][i].clone()
+            } else {
+                BigInt::zero()
+            };
+
+            // diagonal y_i = r (mod n) needs gcd(diagonal, n) | r
+            let g = diagonal.gcd(n);
+            if !r.is_multiple_of(&g) {
+                return None;
+            }
+            if diagonal.is_zero() {
+                continue;
+            }
+
+            let reduced_n = n / &g;
+            y[i] = if reduced_n.is_one() {
+                BigInt::zero()
+            } else {
+                let unit = inverse(&(&diagonal / &g), &reduced_n)?;
+                (r / &g * unit).mod_floor(&reduced_n)
+            };
+        }
+
+        Some(v.mul_vector(&y))
+    }
+
+    // A basis of the vectors x with A x = 0, for a prime modulus. None if a pivot turns out not
+    // to be invertible, which means n wasn't prime. With n = 2 the basis vectors pick out sets of
+    // rows of the transpose adding up to zero, the combinations a factoring sieve is after.
+    pub fn kernel(&self) -> Option<Vec<Vec<BigInt>>> {
+        let (reduced, pivots) = self.reduced_row_echelon()?;
+        let columns = self.column_count();
+
+        let basis = (0..columns)
+            .filter(|j| !pivots.contains(j))
+            .map(|free| {
+                let mut x = vec![BigInt::zero(); columns];
+                x[free] = BigInt::one();
+                for (row, &pivot) in pivots.iter().enumerate() {
+                    x[pivot] = (-&reduced.rows[row][free]).mod_floor(&self.modulus);
+                }
+                x
+            })
+            .collect();
+
+        Some(basis)
+    }
+}
+
+impl ModMatrix {
+    fn combine<F: Fn(&BigInt, &BigInt) -> BigInt>(&self, other: &ModMatrix, f: F) -> ModMatrix {
+        assert!(
+            self.row_count() == other.row_count() && self.column_count() == other.column_count(),
+            "the matrices need the same shape"
+        );
+
+        let combined: Vec<Vec<BigInt>> = self
+            .rows
+            .iter()
+            .zip(other.rows.iter())
+            .map(|(a, b)| a.iter().zip(b.iter()).map(|(x, y)| f(x, y)).collect())
+            .collect();
+
+        ModMatrix::new(&combined, &self.modulus)
+    }
+
+    // Echelon form, pivot columns and the number of row swaps
+    fn eliminate(&self) -> (ModMatrix, Vec<usize>, usize) {
+        let n = &self.modulus;
+        let mut rows = self.rows.clone();
+        let mut pivots = Vec::new();
+        let mut swaps = 0;
+
+        for column in 0..self.column_count() {
+            let k = pivots.len();
+            let nonzero = match (k..rows.len()).find(|&i| !rows[i][column].is_zero()) {
+                Some(i) => i,
+                None => continue,
+            };
+            if nonzero != k {
+                rows.swap(nonzero, k);
+                swaps += 1;
+            }
+
+            for i in k + 1..rows.len() {
+                if !rows[i][column].is_zero() {
+                    let step = elimination_step(&rows[k][column], &rows[i][column]);
+                    mix_rows(&mut rows, k, i, &step, n);
+                }
+            }
+            pivots.push(column);
+        }
+
+        (ModMatrix::new(&rows, n), pivots, swaps)
+    }
+
+    // Echelon form with every pivot scaled to 1 and cleared above, None if a pivot isn't a unit
+    fn reduced_row_echelon(&self) -> Option<(ModMatrix, Vec<usize>)> {
+        let n = &self.modulus;
+        let (echelon, pivots) = self.row_echelon();
+        let mut rows = echelon.rows;
+
+        for (k, &column) in pivots.iter().enumerate() {
+            let unit = inverse(&rows[k][column], n)?;
+            for x in rows[k].iter_mut() {
+                *x = (&*x * &unit).mod_floor(n);
+            }
+
+            for i in 0..k {
+                let factor = rows[i][column].clone();
+                let pivot_row = rows[k].clone();
+                for (x, p) in rows[i].iter_mut().zip(pivot_row.iter()) {
+                    *x = (&*x - &factor * p).mod_floor(n);
+                }
+            }
+        }
+
+        Some((ModMatrix::new(&rows, n), pivots))
+    }
+
+    // (U, D, V) with D = U A V diagonal and U, V invertible
+    fn smith(&self) -> (ModMatrix, ModMatrix, ModMatrix) {
+        let n = &self.modulus;
+        let (row_count, column_count) = (self.row_count(), self.column_count());
+        let mut d = self.rows.clone();
+        let mut u = ModMatrix::identity(row_count, n).rows;
+        let mut v = ModMatrix::identity(column_count, n).rows;
+
+        for k in 0..row_count.min(column_count) {
+            let pivot = (k..row_count)
+                .flat_map(|i| (k..column_count).map(move |j| (i, j)))
+                .find(|&(i, j)| !d[i][j].is_zero());
+            let (i, j) = match pivot {
+                Some(position) => position,
+                None => break,
+            };
+            d.swap(i, k);
+            u.swap(i, k);
+            swap_columns(&mut d, j, k);
+            swap_columns(&mut v, j, k);
+
+            // Clearing the row can refill the column, but the pivot shrinks to a gcd every time
+            loop {
+                for i in k + 1..row_count {
+                    if !d[i][k].is_zero() {
+                        let step = elimination_step(&d[k][k], &d[i][k]);
+                        mix_rows(&mut d, k, i, &step, n);
+                        mix_rows(&mut u, k, i, &step, n);
+                    }
+                }
+                for j in k + 1..column_count {
+                    if !d[k][j].is_zero() {
+                        let step = elimination_step(&d[k][k], &d[k][j]);
+                        mix_columns(&mut d, k, j, &step, n);
+                        mix_columns(&mut v, k, j, &step, n);
+                    }
+                }
+                if (k + 1..row_count).all(|i| d[i][k].is_zero()) {
+                    break;
+                }
+            }
+        }
+
+        (
+            ModMatrix::new(&u, n),
+            ModMatrix::new(&d, n),
+            ModMatrix::new(&v, n),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_mod_matrix {
+    use super::*;
+
+    fn matrix(rows: &[&[i64]], n: i64) -> ModMatrix {
+        let rows: Vec<Vec<BigInt>> = rows
+            .iter()
+            .map(|row| row.iter().map(|&x| BigInt::from(x)).collect())
+            .collect();
+        ModMatrix::new(&rows, &BigInt::from(n))
+    }
+
+    fn ints(xs: &[i64]) -> Vec<BigInt> {
+        xs.iter().map(|&x| BigInt::from(x)).collect()
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = matrix(&[&[1, 2], &[3, 4]], 5);
+        let b = matrix(&[&[4, 4], &[0, 1]], 5);
+
+        assert_eq!(a.add(&b), matrix(&[&[0, 1], &[3, 0]], 5));
+        assert_eq!(a.sub(&b), matrix(&[&[2, 3], &[3, 3]], 5));
+        assert_eq!(a.mul(&b), matrix(&[&[4, 1], &[2, 1]], 5));
+        assert_eq!(a.scale(&BigInt::from(2)), matrix(&[&[2, 4], &[1, 3]], 5));
+        assert_eq!(a.transpose(), matrix(&[&[1, 3], &[2, 4]], 5));
+        assert_eq!(a.mul_vector(&ints(&[1, 1])), ints(&[3, 2]));
+        assert_eq!(a.pow(3), a.mul(&a).mul(&a));
+        assert_eq!(a.pow(0), ModMatrix::identity(2, &BigInt::from(5)));
+    }
+
+    #[test]
+    fn determinant() {
+        assert_eq!(
+            matrix(&[&[6, 24, 1], &[13, 16, 10], &[20, 17, 15]], 26).determinant(),
+            BigInt::from(25)
+        );
+        // det = -2 with a row swap on the way
+        assert_eq!(
+            matrix(&[&[0, 1], &[2, 3]], 1000).determinant(),
+            BigInt::from(998)
+        );
+        assert_eq!(matrix(&[&[2, 4], &[1, 2]], 7).determinant(), BigInt::zero());
+        // -14, with a first pivot of 2 that has no inverse mod 12
+        assert_eq!(
+            matrix(&[&[2, 4], &[5, 3]], 12).determinant(),
+            BigInt::from(10)
+        );
+    }
+
+    #[test]
+    fn inverse() {
+        let hill = matrix(&[&[6, 24, 1], &[13, 16, 10], &[20, 17, 15]], 26);
+        let inverse = hill.inverse().unwrap();
+
+        assert_eq!(
+            inverse,
+            matrix(&[&[8, 5, 10], &[21, 8, 21], &[21, 12, 8]], 26)
+        );
+        assert_eq!(
+            hill.mul(&inverse),
+            ModMatrix::identity(3, &BigInt::from(26))
+        );
+        // det = 4 shares a factor with 26
+        assert_eq!(matrix(&[&[2, 2], &[1, 3]], 26).inverse(), None);
+    }
+
+    #[test]
+    fn empty() {
+        let empty = matrix(&[], 26);
+        assert_eq!(empty.determinant(), BigInt::one());
+        assert_eq!(empty.inverse(), Some(empty));
+    }
+
+    #[test]
+    #[should_panic(expected = "modulus needs to be positive")]
+    fn zero_modulus() {
+        matrix(&[&[1]], 0);
+    }
+
+    #[test]
+    fn row_echelon() {
+        let (echelon, pivots) = matrix(&[&[0, 2, 4], &[0, 1, 2], &[1, 1, 1]], 7).row_echelon();
+
+        assert_eq!(pivots, vec![0, 1]);
+        assert_eq!(echelon.rows[2], ints(&[0, 0, 0]));
+        assert!(echelon.rows[1][0].is_zero());
+    }
+
+    #[test]
+    fn solve_prime() {
+        // x + 2y = 5, 3x + 4y = 6 mod 7
+        let a = matrix(&[&[1, 2], &[3, 4]], 7);
+        let x = a.solve(&ints(&[5, 6])).unwrap();
+        assert_eq!(a.mul_vector(&x), ints(&[5, 6]));
+    }
+
+    #[test]
+    fn solve_composite() {
+        // Singular mod 12 but still solvable for some right hand sides
+        let a = matrix(&[&[2, 4], &[4, 2]], 12);
+        let x = a.solve(&ints(&[6, 0])).unwrap();
+        assert_eq!(a.mul_vector(&x), ints(&[6, 0]));
+
+        // 2x + 4y is always even
+        assert_eq!(a.solve(&ints(&[1, 0])), None);
+
+        // More equations than unknowns, the last one has to agree with the others
+        let tall = matrix(&[&[1, 1], &[1, 2], &[2, 3]], 15);
+        let x = tall.solve(&ints(&[3, 5, 8])).unwrap();
+        assert_eq!(x, ints(&[1, 2]));
+        assert_eq!(tall.solve(&ints(&[3, 5, 9])), None);
+    }
+
+    #[test]
+    fn solve_large() {
+        let n = string_to_number("340282366920938463463374607431768211457");
+        let rows: Vec<Vec<BigInt>> = vec![ints(&[3, 1, 4]), ints(&[1, 5, 9]), ints(&[2, 6, 5])];
+        let a = ModMatrix::new(&rows, &n);
+        let b = vec![
+            string_to_number("123456789012345678901234567890"),
+            string_to_number("987654321098765432109876543210"),
+            BigInt::from(42),
+        ];
+
+        let x = a.solve(&b).unwrap();
+        assert_eq!(a.mul_vector(&x), b);
+    }
+
+    #[test]
+    fn gf2_kernel() {
+        // Quadratic sieve for 1649: 41^2 = 2^5 and 43^2 = 2^3 5^2 mod 1649. With one row of
+        // exponents mod 2 over the primes 2, 3, 5 per relation, a kernel vector of the transpose
+        // picks relations whose product is a square.
+        let exponents = matrix(&[&[1, 0, 0], &[1, 0, 0]], 2);
+        let kernel = exponents.transpose().kernel().unwrap();
+        assert_eq!(kernel, vec![ints(&[1, 1])]);
+
+        // So (41 * 43)^2 = 2^8 5^2 = 80^2 (mod 1649), and gcd(41 * 43 - 80, 1649) = 17
+        let x = BigInt::from(41 * 43 - 80);
+        assert_eq!(x.gcd(&BigInt::from(1649)), BigInt::from(17));
+
+        assert_eq!(
+            matrix(&[&[1, 1, 0], &[0, 1, 1]], 2).kernel().unwrap(),
+            vec![ints(&[1, 1, 1])]
+        );
+        assert_eq!(matrix(&[&[1, 0], &[0, 1]], 2).kernel().unwrap().len(), 0);
+        // Not prime: 2 can't be scaled to 1 mod 4
+        assert_eq!(matrix(&[&[2, 1]], 4).kernel(), None);
+    }
+}
@@ -0,0 +1,728 @@
+// Attacks that recover an RSA plaintext without the private key, by exploiting how textbook RSA
+// was used rather than factoring n: a small e sent to several recipients, the same message under
+// two exponents, related messages, a known prefix found with Coppersmith's method, and ciphertexts
+// encrypted one byte at a time.
+
+use crypto_math::{extended_gcd, mod_inverse, number_to_string, string_to_number, ONE, ZERO};
+use lattice;
+use montgomery::MontgomeryContext;
+use num::{
+    bigint::{BigInt, ToBigInt},
+    pow, Integer,
+};
+use num_traits::ToPrimitive;
+use number_theory::{chinese_remainder_num, integer_root_num, modulo};
+use polynomial::{self, ModPolynomial};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+// The message behind residues[i] = m^e (mod moduli[i]): combined with the CRT this is m^e modulo
+// the product of the moduli, and when m^e is smaller than that an ordinary e-th root gives m
+fn hastad_broadcast_num(residues: &[BigInt], moduli: &[BigInt], e: u32) -> Option<BigInt> {
+    let (x, _) = chinese_remainder_num(residues, moduli)?;
+    let m = integer_root_num(&x, e);
+
+    if pow(m.clone(), e as usize) == x {
+        Some(m)
+    } else {
+        None
+    }
+}
+
+// Håstad's broadcast attack: the same message sent with exponent e to e different recipients can
+// be read without any private key. Takes ciphertexts in the format encrypt produces, one per
+// modulus, and returns the plaintext or None if the ciphertexts don't line up.
+//
+// encrypt works byte by byte, so with e = 3 each m^3 < 2^24 is already smaller than any one
+// modulus and a single ciphertext would do. The CRT step is what carries the attack over to
+// messages too big for that, which is also why padding every message differently stops it.
+// Ref: https://en.wikipedia.org/wiki/Coppersmith%27s_attack#H%C3%A5stad%27s_broadcast_attack
+pub fn hastad_broadcast_attack(ciphertexts: &[&str], moduli: &[&str], e: u32) -> Option<String> {
+    if ciphertexts.is_empty() || ciphertexts.len() != moduli.len() || e == 0 {
+        return None;
+    }
+
+    let moduli: Vec<BigInt> = moduli.iter().map(|n| string_to_number(n)).collect();
+
+    // encrypt puts a comma in front of every value
+    let blocks: Vec<Vec<BigInt>> = ciphertexts
+        .iter()
+        .map(|c| {
+            c.split(',')
+                .filter(|v| !v.is_empty())
+                .map(string_to_number)
+                .collect()
+        })
+        .collect();
+
+    if blocks.iter().any(|b| b.len() != blocks[0].len()) {
+        return None;
+    }
+
+    let mut plaintext = String::default();
+
+    for i in 0..blocks[0].len() {
+        let residues: Vec<BigInt> = blocks.iter().map(|b| b[i].clone()).collect();
+        let m = hastad_broadcast_num(&residues, &moduli, e)?;
+        plaintext.push(m.to_u8()? as char);
+    }
+
+    Some(plaintext)
+}
+
+#[cfg(test)]
+mod test_hastad_broadcast_attack {
+    use super::*;
+    use crypto_math::{encrypt, Keypair};
+
+    fn keypairs() -> Vec<Keypair> {
+        let seeds: Vec<Vec<u8>> = (1..4u8).map(|i| vec![i * 16; 32]).collect();
+        (0..3)
+            .map(|i| Keypair::new_with_exponent(&seeds[i], &seeds[(i + 1) % 3], 3))
+            .collect()
+    }
+
+    #[test]
+    fn new_with_exponent() {
+        for k in keypairs() {
+            assert_eq!(k.e(), "3");
+            assert_eq!(k.decrypt(&encrypt("e = 3", &k.e(), &k.n())[1..]), "e = 3");
+        }
+    }
+
+    #[test]
+    fn three_recipients() {
+        let keys = keypairs();
+        let ciphertexts: Vec<String> = keys
+            .iter()
+            .map(|k| encrypt("HelloWorld!", &k.e(), &k.n()))
+            .collect();
+        let c: Vec<&str> = ciphertexts.iter().map(|c| c.as_str()).collect();
+        let moduli: Vec<String> = keys.iter().map(|k| k.n()).collect();
+        let n: Vec<&str> = moduli.iter().map(|n| n.as_str()).collect();
+
+        assert_eq!(
+            hastad_broadcast_attack(&c, &n, 3),
+            Some("HelloWorld!".to_string())
+        );
+    }
+
+    #[test]
+    fn mismatched_ciphertexts() {
+        let keys = keypairs();
+        let a = encrypt("Hello", &keys[0].e(), &keys[0].n());
+        let b = encrypt("World!", &keys[1].e(), &keys[1].n());
+
+        assert_eq!(
+            hastad_broadcast_attack(&[&a, &b], &[&keys[0].n(), &keys[1].n()], 3),
+            None
+        );
+        assert_eq!(hastad_broadcast_attack(&[&a], &[], 3), None);
+    }
+
+    #[test]
+    fn message_larger_than_each_modulus() {
+        // m^3 is bigger than any two of the 64-bit moduli multiplied together, so it needs all three
+        let m = string_to_number("12345678901234567890");
+        let moduli: Vec<BigInt> = [
+            "18446744073709551557",
+            "18446744073709551533",
+            "18446744073709551521",
+        ]
+        .iter()
+        .map(|n| string_to_number(n))
+        .collect();
+        let residues: Vec<BigInt> = moduli
+            .iter()
+            .map(|n| m.modpow(&string_to_number("3"), n))
+            .collect();
+
+        assert_eq!(hastad_broadcast_num(&residues, &moduli, 3), Some(m));
+        assert_eq!(hastad_broadcast_num(&residues[..2], &moduli[..2], 3), None);
+    }
+}
+
+// c^x mod n for a possibly negative x, going through c^-1 when x < 0
+fn signed_modpow(c: &BigInt, x: &BigInt, n: &BigInt) -> Option<BigInt> {
+    if *x >= *ZERO {
+        return Some(c.modpow(x, n));
+    }
+
+    let inverse = string_to_number(&mod_inverse(&number_to_string(c), &number_to_string(n))?);
+    Some(modulo(&inverse, n).modpow(&-x, n))
+}
+
+fn common_modulus_num(
+    c1: &BigInt,
+    c2: &BigInt,
+    e1: &BigInt,
+    e2: &BigInt,
+    n: &BigInt,
+) -> Option<BigInt> {
+    if *e1 <= *ZERO || *e2 <= *ZERO || *n <= *ONE || e1.gcd(e2) != *ONE {
+        return None;
+    }
+
+    // x e1 + y e2 = 1, so c1^x c2^y = m
+    let (x, y) = extended_gcd(&number_to_string(e1), &number_to_string(e2));
+
+    let c1_x = signed_modpow(&modulo(c1, n), &string_to_number(&x), n)?;
+    let c2_y = signed_modpow(&modulo(c2, n), &string_to_number(&y), n)?;
+
+    Some(c1_x * c2_y % n)
+}
+
+// Common-modulus attack: the same m encrypted under one n with two coprime exponents comes back
+// without the private key, since x e1 + y e2 = 1 makes c1^x c2^y = m. Returns None unless both
+// exponents are positive and coprime, or when c1 or c2 has no inverse mod n.
+// Ref: https://crypto.stackexchange.com/questions/16283/how-to-use-common-modulus-attack
+pub fn common_modulus_attack(c1: &str, c2: &str, e1: &str, e2: &str, n: &str) -> Option<String> {
+    common_modulus_num(
+        &string_to_number(c1),
+        &string_to_number(c2),
+        &string_to_number(e1),
+        &string_to_number(e2),
+        &string_to_number(n),
+    )
+    .map(|m| number_to_string(&m))
+}
+
+#[cfg(test)]
+mod test_common_modulus_attack {
+    use super::*;
+
+    #[test]
+    fn tiny() {
+        assert_eq!(
+            common_modulus_attack("2790", "1317", "17", "7", "3233"),
+            Some("65".to_string())
+        );
+    }
+
+    #[test]
+    fn exponents_not_coprime() {
+        // 10^6 and 10^9 only give 10^3
+        assert_eq!(common_modulus_attack("1003", "770", "6", "9", "3233"), None);
+        assert_eq!(
+            common_modulus_attack("2790", "1317", "0", "0", "3233"),
+            None
+        );
+        assert_eq!(
+            common_modulus_attack("2790", "1317", "-17", "7", "3233"),
+            None
+        );
+    }
+
+    #[test]
+    fn large() {
+        let n = "8715031786012211408378924538969986070250176789724519635696270121434970412231274783491786008648431213672947682553322415637962920966700275053802992813974259";
+        let c1 = "1779619723435960422336925101228859902779466179660313722664545743777191887587210659185348568631145473906318090151579281078786889888081554640684559415507500";
+        let c2 = "4836889564177094754513968217679540763960067167011434320944518522967608081265102346296911483050464647549329633014473922854542904098082809589224583178137416";
+        assert_eq!(
+            common_modulus_attack(c1, c2, "65537", "257", n),
+            Some(
+                "124468720361391282510286578559360201830505808500462350136301832685645555557"
+                    .to_string()
+            )
+        );
+
+        // e1 = 9 and e2 = 15 share a factor of 3
+        let c1 = "8217635027360698272867202532682914496455783715218669848619220389282382848547169576990772636632773308930668433591469599234398183975257575559875590360013803";
+        let c2 = "4750474319781869866434043322591207065074992315361161059808553210166647508944231841637777187888844490213604261142838829893874247901927179638718821937082229";
+        assert_eq!(common_modulus_attack(c1, c2, "9", "15", n), None);
+    }
+}
+
+fn franklin_reiter_num(
+    c1: &BigInt,
+    c2: &BigInt,
+    a: &BigInt,
+    b: &BigInt,
+    e: u32,
+    n: &BigInt,
+) -> Option<BigInt> {
+    // m is a root of both x^e - c1 and (ax + b)^e - c2, so x - m divides their gcd
+    let f1 = ModPolynomial::monomial(e as usize, n).sub(&ModPolynomial::constant(c1, n));
+    let f2 = ModPolynomial::new(&[b.clone(), a.clone()], n)
+        .pow(e)
+        .sub(&ModPolynomial::constant(c2, n));
+
+    let g = f1.gcd(&f2)?;
+    if g.degree() != Some(1) {
+        return None;
+    }
+
+    // g is monic, x + g_0
+    let m = modulo(&-&g.coefficients()[0], n);
+    if m.modpow(&e.to_bigint().unwrap(), n) == modulo(c1, n) {
+        Some(m)
+    } else {
+        None
+    }
+}
+
+// Franklin–Reiter related-message attack: given c1 = m^e and c2 = (am + b)^e under the same key,
+// m is the common root of x^e - c1 and (ax + b)^e - c2, and for small e their gcd is almost always
+// exactly x - m. Polynomial arithmetic costs grow like e^2, so this is for e = 3 and friends.
+// Ref: https://en.wikipedia.org/wiki/Coppersmith%27s_attack#Franklin%E2%80%93Reiter_related-message_attack
+pub fn franklin_reiter_attack(
+    c1: &str,
+    c2: &str,
+    a: &str,
+    b: &str,
+    e: u32,
+    n: &str,
+) -> Option<String> {
+    franklin_reiter_num(
+        &string_to_number(c1),
+        &string_to_number(c2),
+        &string_to_number(a),
+        &string_to_number(b),
+        e,
+        &string_to_number(n),
+    )
+    .map(|m| number_to_string(&m))
+}
+
+#[cfg(test)]
+mod test_franklin_reiter_attack {
+    use super::*;
+
+    const N: &str = "8715031786012211408378924538969986070250176789724519635696270121434970412231274783491786008648431213672947682553322415637962920966700275053802992813974259";
+    const C1: &str = "7366952909647775469264181598793687035068210965303254640718693267936389932190969258493827856898274728213392541170534831027761999262723281898427122079790801";
+    const M: &str = "124468720361391282510286578559360201830505808500462350136301832685645555557";
+
+    #[test]
+    fn shifted_message() {
+        // m2 = m + 2^64 + 7
+        let c2 = "3897784159846600134500887473148276753141144993177678923643583578937535988637243050388970873773136428385856702646639133682476452408410997813153302346317093";
+        assert_eq!(
+            franklin_reiter_attack(C1, c2, "1", "18446744073709551623", 3, N),
+            Some(M.to_string())
+        );
+    }
+
+    #[test]
+    fn affine_relation() {
+        // m2 = 3m + 12345
+        let c2 = "3625867569866761515712684402055662997701757036470049433400181010132428196125159709193558076918120800617855242813598958689444724437728016632527766930790055";
+        assert_eq!(
+            franklin_reiter_attack(C1, c2, "3", "12345", 3, N),
+            Some(M.to_string())
+        );
+    }
+
+    #[test]
+    fn wrong_relation() {
+        let c2 = "3625867569866761515712684402055662997701757036470049433400181010132428196125159709193558076918120800617855242813598958689444724437728016632527766930790055";
+        assert_eq!(franklin_reiter_attack(C1, c2, "3", "12346", 3, N), None);
+    }
+}
+
+// Maps every value encrypt can produce for (e, n) back to the byte it came from
+fn codebook(e: &BigInt, n: &BigInt) -> HashMap<BigInt, u8> {
+    let context = MontgomeryContext::new(n);
+
+    (0..256u32)
+        .map(|b| {
+            let m = b.to_bigint().unwrap();
+            let c = match context {
+                Some(ref context) => context.pow(&m, e),
+                None => m.modpow(e, n),
+            };
+            (c, b as u8)
+        })
+        .collect()
+}
+
+// Codebook attack on encrypt: textbook RSA is deterministic and encrypt works one byte at a time,
+// so encrypting all 256 bytes with the public key gives a table that decodes any message sent to
+// it. Values missing from the table (not made by encrypt under this key) come out as '?'.
+#[wasm_bindgen]
+pub fn codebook_attack(ciphertext: &str, e: &str, n: &str) -> String {
+    let table = codebook(&string_to_number(e), &string_to_number(n));
+
+    ciphertext
+        .split(',')
+        .filter(|c| !c.is_empty())
+        .map(|c| match table.get(&string_to_number(c)) {
+            Some(&b) => b as char,
+            None => '?',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_codebook_attack {
+    use super::*;
+    use crypto_math::{encrypt, test_seed, Keypair};
+
+    #[test]
+    fn recovers_message() {
+        let k = Keypair::new(test_seed(), &[7; 32]);
+        let (e, n) = (k.e(), k.n());
+        let ciphertext = encrypt("Meet at the docks at 9pm.", &e, &n);
+
+        assert_eq!(
+            codebook_attack(&ciphertext, &e, &n),
+            "Meet at the docks at 9pm."
+        );
+        // Also without encrypt's leading comma
+        assert_eq!(
+            codebook_attack(&ciphertext[1..], &e, &n),
+            "Meet at the docks at 9pm."
+        );
+    }
+
+    #[test]
+    fn unknown_values() {
+        assert_eq!(codebook_attack(",2790,1234,2790", "17", "3233"), "A?A");
+    }
+}
+
+// Characters of English prose from most to least common, spaces first
+// Ref: https://en.wikipedia.org/wiki/Letter_frequency
+static ENGLISH_FREQUENCY_ORDER: &[u8] =
+    b" etaoinshrdlcumwfgypbvk.,TAISHWMBCjxqz'OEDNLRPFGY-!?\"UVKJXQZ0123456789";
+
+// Ref: https://en.wikipedia.org/wiki/Most_common_words_in_English
+static COMMON_WORDS: &[&str] = &[
+    "the", "of", "and", "to", "a", "in", "is", "it", "you", "that", "was", "for", "on", "are",
+    "with", "as", "i", "his", "they", "be", "at", "one", "have", "this", "from", "or", "had", "by",
+    "not", "but", "what", "all", "were", "we", "when", "your", "can", "said", "there", "use", "an",
+    "each", "which", "she", "do", "how", "their", "if", "will", "up", "other", "about", "out",
+    "many", "then", "them", "these", "so", "some", "her", "would", "make", "like", "him", "into",
+    "time", "has", "look", "two", "more",
+];
+
+// Ref: https://en.wikipedia.org/wiki/Bigram#Bigram_frequency_in_the_English_language
+static COMMON_BIGRAMS: &[&[u8]] = &[
+    b"th", b"he", b"in", b"er", b"an", b"re", b"on", b"at", b"en", b"nd", b"ti", b"es", b"or",
+    b"te", b"of", b"ed", b"is", b"it", b"al", b"ar", b"st", b"to", b"nt", b"ng", b"se", b"ha",
+    b"as", b"ou", b"io", b"le", b"ve", b"co", b"me", b"de", b"hi", b"ri", b"ro", b"ic", b"ne",
+    b"ea", b"ra", b"ce", b"li", b"ch", b"ll", b"be", b"ma", b"si", b"om", b"ur",
+];
+
+// How English a guess looks: points for every common word, weighted by length, and for every
+// common bigram
+fn english_score(text: &[u8]) -> usize {
+    let words: usize = text
+        .split(|&c| c == b' ')
+        .filter(|w| COMMON_WORDS.iter().any(|c| c.as_bytes() == *w))
+        .map(|w| 2 * w.len())
+        .sum();
+    let bigrams = text
+        .windows(2)
+        .filter(|pair| COMMON_BIGRAMS.contains(pair))
+        .count();
+
+    words + bigrams
+}
+
+// Codebook attack without the public key: every byte always encrypts to the same value, so the
+// most frequent value is most likely a space, the next an 'e', and so on. That first guess is
+// then improved by swapping pairs of letters while the text scores as more English. Longer
+// messages come out readable, short ones are a starting point to fix up by hand.
+// Ref: https://en.wikipedia.org/wiki/Frequency_analysis
+#[wasm_bindgen]
+pub fn frequency_attack(ciphertext: &str) -> String {
+    let values: Vec<&str> = ciphertext.split(',').filter(|c| !c.is_empty()).collect();
+
+    // Distinct values by count, ties broken by first appearance so the guess is deterministic
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for &v in &values {
+        match counts.iter_mut().find(|&&mut (c, _)| c == v) {
+            Some(entry) => entry.1 += 1,
+            None => counts.push((v, 1)),
+        }
+    }
+    counts.sort_by_key(|&(_, count)| ::std::cmp::Reverse(count));
+
+    // symbols[i] is the rank of the i-th value, guess[rank] the byte that rank decodes to
+    let symbols: Vec<usize> = values
+        .iter()
+        .map(|&v| counts.iter().position(|&(c, _)| c == v).unwrap())
+        .collect();
+    let mut guess: Vec<u8> = (0..counts.len())
+        .map(|rank| *ENGLISH_FREQUENCY_ORDER.get(rank).unwrap_or(&b'?'))
+        .collect();
+
+    let decode = |guess: &[u8]| -> Vec<u8> { symbols.iter().map(|&s| guess[s]).collect() };
+    let mut best = english_score(&decode(&guess));
+
+    // Hill climbing over swaps, leaving the space where it is since the words hang off it
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 1..guess.len() {
+            for j in i + 1..guess.len() {
+                guess.swap(i, j);
+                let score = english_score(&decode(&guess));
+                if score > best {
+                    best = score;
+                    improved = true;
+                } else {
+                    guess.swap(i, j);
+                }
+            }
+        }
+    }
+
+    decode(&guess).iter().map(|&b| b as char).collect()
+}
+
+#[cfg(test)]
+mod test_frequency_attack {
+    use super::*;
+    use crypto_math::encrypt;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(frequency_attack(""), "");
+        assert_eq!(frequency_attack(",5,9,5"), " e ");
+    }
+
+    #[test]
+    fn english_text() {
+        let plaintext = "meet me at the old mill after dark and bring the documents with you we \
+                         will need them to prove what the council has been hiding from the people \
+                         of this town for many years";
+        let ciphertext = encrypt(plaintext, "17", "3233");
+        let guess = frequency_attack(&ciphertext);
+
+        // Not perfect, but most of it comes back without ever seeing the key
+        let correct = guess
+            .chars()
+            .zip(plaintext.chars())
+            .filter(|&(g, p)| g == p)
+            .count();
+        assert_eq!(guess.len(), plaintext.len());
+        assert!(guess.starts_with("meet me "));
+        assert!(correct * 2 > plaintext.len());
+    }
+}
+
+// Largest m coppersmith_small_roots will use, the lattice has dimension deg(f) m + 1 and exact LLL
+// on it slows down quickly: m = 2 takes seconds for a cubic mod a 512-bit n, m = 3 most of a minute
+const COPPERSMITH_MAX_M: usize = 3;
+
+// Coppersmith's method in Howgrave-Graham's form for a monic f of degree delta: the polynomials
+// x^j n^(m - i) f^i (i < m, j < delta) and x^j f^m (j < t) all vanish mod n^m at a small root
+// x0. LLL on their coefficients, scaled by powers of the bound X, finds a combination with small
+// enough coefficients that it vanishes at x0 over the integers, where the root is easy to find.
+// Ref: https://en.wikipedia.org/wiki/Coppersmith_method
+fn coppersmith_num(f: &ModPolynomial, bound: &BigInt, m: usize, t: usize) -> Vec<BigInt> {
+    let n = f.modulus();
+    let delta = match f.degree() {
+        Some(degree) if degree > 0 => degree,
+        _ => return Vec::new(),
+    };
+    let dimension = delta * m + t;
+
+    let f_integer = f.coefficients().to_vec();
+    let mut f_powers = vec![vec![ONE.clone()]];
+    for i in 0..m {
+        let next = polynomial::integer_multiply(&f_powers[i], &f_integer);
+        f_powers.push(next);
+    }
+
+    let mut shifts: Vec<(usize, BigInt, usize)> = Vec::new();
+    for i in 0..m {
+        for j in 0..delta {
+            shifts.push((i, pow(n.clone(), m - i), j));
+        }
+    }
+    for j in 0..t {
+        shifts.push((m, ONE.clone(), j));
+    }
+
+    // Row k holds the coefficients of g(xX) for the k-th shifted polynomial g, lower triangular
+    let basis: Vec<Vec<BigInt>> = shifts
+        .iter()
+        .map(|&(i, ref scale, j)| {
+            let mut row = vec![ZERO.clone(); dimension];
+            for (power, c) in f_powers[i].iter().enumerate() {
+                row[power + j] = scale * c * pow(bound.clone(), power + j);
+            }
+            row
+        })
+        .collect();
+
+    let reduced = lattice::lll_reduce(&basis);
+
+    // Undo the scaling by X to get back an ordinary polynomial with the same small roots
+    let g: Vec<BigInt> = reduced[0]
+        .iter()
+        .enumerate()
+        .map(|(power, c)| c / pow(bound.clone(), power))
+        .collect();
+
+    polynomial::integer_roots(&g, bound)
+        .into_iter()
+        .filter(|x| f.evaluate(x) == *ZERO)
+        .map(|x| modulo(&x, n))
+        .collect()
+}
+
+// Smallest m for which LLL is guaranteed to find a root below bound: with dimension d = delta m + 1
+// the reduced vector is short enough once det^(1/d) 2^((d - 1) / 4) sqrt(d) < n^m, where the
+// lattice determinant is n^(delta m (m + 1) / 2) X^(d (d - 1) / 2). Worked out in logs since
+// the numbers themselves are huge.
+fn coppersmith_m(delta: usize, n: &BigInt, bound: &BigInt) -> Option<usize> {
+    let log_n = n.bits() as f64;
+    let log_x = bound.bits() as f64;
+
+    (1..COPPERSMITH_MAX_M + 1).find(|&m| {
+        let d = (delta * m + 1) as f64;
+        let log_det = (delta * m * (m + 1) / 2) as f64 * log_n + d * (d - 1.0) / 2.0 * log_x;
+
+        log_det / d + (d - 1.0) / 4.0 + d.log2() / 2.0 < m as f64 * log_n
+    })
+}
+
+// Roots x of f mod n with |x| <= bound, for bounds up to about n^(1 / deg(f)). coefficients are
+// given constant term first, and f is made monic first so its leading coefficient has to be
+// invertible mod n. Returns nothing when the bound is too large for COPPERSMITH_MAX_M.
+pub fn coppersmith_small_roots(coefficients: &[&str], n: &str, bound: &str) -> Vec<String> {
+    let n_num = string_to_number(n);
+    let coefficients: Vec<BigInt> = coefficients.iter().map(|c| string_to_number(c)).collect();
+    let bound_num = string_to_number(bound);
+
+    let f = match ModPolynomial::new(&coefficients, &n_num).monic() {
+        Some(f) => f,
+        None => return Vec::new(),
+    };
+
+    let delta = f.degree().unwrap_or(0);
+    match coppersmith_m(delta, &n_num, &bound_num) {
+        Some(m) => coppersmith_num(&f, &bound_num, m, 1)
+            .iter()
+            .map(number_to_string)
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod test_coppersmith_small_roots {
+    use super::*;
+
+    #[test]
+    fn tiny() {
+        // (x - 3)(x - 500) mod 10007, only 3 is below the bound
+        assert_eq!(
+            coppersmith_small_roots(&["1500", "-503", "1"], "10007", "10"),
+            vec!["3"]
+        );
+    }
+
+    #[test]
+    fn non_monic() {
+        // 2x - 14 has the root 7
+        assert_eq!(
+            coppersmith_small_roots(&["-14", "2"], "1000003", "100"),
+            vec!["7"]
+        );
+        assert!(coppersmith_small_roots(&["-14", "2"], "1000004", "100").is_empty());
+    }
+
+    #[test]
+    fn large() {
+        // f(x) = x^2 + a x + b with a small root hidden mod a 512-bit n
+        let n = string_to_number("10161817494405959198518212102682698047823376890036129423280526891359096045400978071832046041560906479654599347421965467635459277788284803346706357962228231");
+        let x0 = string_to_number("98765432109876543210");
+        let a = string_to_number("31415926535897932384626433832795028841971693993751");
+        let b = modulo(&-(&x0 * &x0 + &a * &x0), &n);
+
+        let roots = coppersmith_small_roots(
+            &[&number_to_string(&b), &number_to_string(&a), "1"],
+            &number_to_string(&n),
+            "100000000000000000000",
+        );
+        assert_eq!(roots, vec![number_to_string(&x0)]);
+    }
+}
+
+// Stereotyped-message attack on textbook RSA with a small e: when all of m but the last
+// unknown_bytes bytes is known, m = P 256^u + x and x is a small root of (P 256^u + x)^e - c.
+// ciphertext is m^e mod n for the whole message as one big-endian number, encrypt's byte-by-byte
+// format is weaker still and falls to codebook_attack.
+// Ref: https://en.wikipedia.org/wiki/Coppersmith%27s_attack#Low_public_exponent_attack
+pub fn stereotyped_message_attack(
+    prefix: &str,
+    unknown_bytes: usize,
+    ciphertext: &str,
+    e: u32,
+    n: &str,
+) -> Option<String> {
+    let n_num = string_to_number(n);
+    let shift = pow(string_to_number("256"), unknown_bytes);
+    let known = BigInt::from_bytes_be(num::bigint::Sign::Plus, prefix.as_bytes()) * &shift;
+
+    let f = ModPolynomial::new(&[known, ONE.clone()], &n_num)
+        .pow(e)
+        .sub(&ModPolynomial::constant(
+            &string_to_number(ciphertext),
+            &n_num,
+        ));
+    let bound = &shift - &*ONE;
+
+    let m = coppersmith_m(e as usize, &n_num, &bound)?;
+    let x = coppersmith_num(&f, &bound, m, 1)
+        .into_iter()
+        .find(|x| *x <= bound)?;
+
+    let (_, mut suffix) = x.to_bytes_be();
+    while suffix.len() < unknown_bytes {
+        suffix.insert(0, 0);
+    }
+    if x == *ZERO {
+        suffix = vec![0; unknown_bytes];
+    }
+
+    let mut message = prefix.as_bytes().to_vec();
+    message.extend_from_slice(&suffix);
+    String::from_utf8(message).ok()
+}
+
+#[cfg(test)]
+mod test_stereotyped_message_attack {
+    use super::*;
+
+    const N: &str = "10161817494405959198518212102682698047823376890036129423280526891359096045400978071832046041560906479654599347421965467635459277788284803346706357962228231";
+
+    #[test]
+    fn eight_unknown_bytes() {
+        let c = "9051741917221464571313807482683070223631246597673395534589049556495451227716481341108143906422656574919186946845678797787941211489608097979400608115918261";
+        assert_eq!(
+            stereotyped_message_attack("The secret code is: ", 8, c, 3, N),
+            Some("The secret code is: Xq7!pZ2k".to_string())
+        );
+    }
+
+    #[test]
+    fn twelve_unknown_bytes() {
+        // 96 unknown bits are past what the smallest lattice can do
+        let c = "2981464584664153879258479534408605254502569241483334659813009715641725928716972513387679684804629369007138869922790988203663438389435618503474417908063768";
+        assert_eq!(
+            stereotyped_message_attack("Launch code: ", 12, c, 3, N),
+            Some("Launch code: 0451-9876-ZZ".to_string())
+        );
+    }
+
+    #[test]
+    fn too_many_unknown_bytes() {
+        // Past n^(1/3), where no choice of m helps
+        let c = "9051741917221464571313807482683070223631246597673395534589049556495451227716481341108143906422656574919186946845678797787941211489608097979400608115918261";
+        assert_eq!(stereotyped_message_attack("The ", 24, c, 3, N), None);
+    }
+
+    #[test]
+    fn wrong_prefix() {
+        let c = "9051741917221464571313807482683070223631246597673395534589049556495451227716481341108143906422656574919186946845678797787941211489608097979400608115918261";
+        assert_eq!(
+            stereotyped_message_attack("The secret code is! ", 8, c, 3, N),
+            None
+        );
+    }
+}
@@ -0,0 +1,486 @@
+use num::{
+    bigint::{BigInt, Sign},
+    Integer, One,
+};
+
+// Montgomery arithmetic for a fixed odd modulus n. Numbers are kept in the form aR mod n with
+// R = 2^r_bits > n, which turns every reduction mod n into shifts and masks (REDC) instead of a
+// long division. Building the context costs one inverse and one reduction, so it pays off as soon
+// as a modulus is used for more than a couple of multiplications: Miller–Rabin rounds, RSA over
+// every byte of a message, and so on. The exponentiations work on the 32-bit digits directly and
+// interleave the multiplication with the reduction, which is what lets them beat modpow.
+// Ref: https://en.wikipedia.org/wiki/Montgomery_modular_multiplication
+#[derive(Clone, Debug)]
+pub struct MontgomeryContext {
+    // The odd modulus
+    n: BigInt,
+    // R = 2^r_bits >= 4n, rounded up to whole 32-bit digits
+    r_bits: usize,
+    // R - 1, so x & mask is x mod R
+    mask: BigInt,
+    // R^2 mod n, used to move numbers into Montgomery form
+    r2: BigInt,
+    // -n^-1 mod R
+    n_prime: BigInt,
+    // n as r_bits / 32 little-endian digits
+    n_digits: Vec<u32>,
+    // -n^-1 mod 2^32, all a digit-by-digit REDC needs of n_prime
+    n0_inv: u32,
+}
+
+impl MontgomeryContext {
+    // Returns None unless n is odd and greater than one
+    pub fn new(n: &BigInt) -> Option<MontgomeryContext> {
+        if *n <= BigInt::one() || n.is_even() {
+            return None;
+        }
+
+        // Two spare bits let the constant-time paths skip the final subtraction in REDC
+        let r_bits = (n.bits() + 2 + 31) & !31;
+        let r = BigInt::one() << r_bits;
+        let mask = &r - BigInt::one();
+
+        // Newton's iteration x = x(2 - nx) doubles the number of correct low bits of n^-1 each
+        // time, starting from n * 1 = 1 (mod 2)
+        let mut inverse = BigInt::one();
+        let mut correct_bits = 1;
+        while correct_bits < r_bits {
+            inverse = (&inverse * (BigInt::from(2) - n * &inverse)) & &mask;
+            correct_bits *= 2;
+        }
+
+        let n_prime = (&r - inverse) & &mask;
+        let r2 = (&r * &r) % n;
+
+        let n_digits = digits(n, r_bits / 32);
+        let n0_inv = digits(&n_prime, 1)[0];
+
+        Some(MontgomeryContext {
+            n: n.clone(),
+            r_bits,
+            mask,
+            r2,
+            n_prime,
+            n_digits,
+            n0_inv,
+        })
+    }
+
+    pub fn modulus(&self) -> &BigInt {
+        &self.n
+    }
+
+    // REDC: t R^-1 mod n for 0 <= t < nR
+    fn reduce(&self, t: &BigInt) -> BigInt {
+        let m = ((t & &self.mask) * &self.n_prime) & &self.mask;
+        let u = (t + m * &self.n) >> self.r_bits;
+
+        if u >= self.n {
+            u - &self.n
+        } else {
+            u
+        }
+    }
+
+    // abR^-1 mod n on digits, one digit of b at a time (the CIOS method): every round adds a * b_i
+    // and the multiple of n that clears the lowest digit, then shifts that digit out.
+    //
+    // The final conditional subtraction is left out, its presence depends on the operands and is
+    // exactly what Kocher-style timing attacks measure. For a, b < 2n the result is below 2n
+    // because R >= 4n, so values in [0, 2n) can be fed straight back in.
+    // Ref: Koç, Acar, Kaliski, "Analyzing and Comparing Montgomery Multiplication Algorithms"
+    fn mul_digits(&self, a: &[u32], b: &[u32]) -> Vec<u32> {
+        let n = &self.n_digits;
+        let s = n.len();
+        let mut t = vec![0u32; s + 2];
+
+        for &b_i in b {
+            let mut carry = 0u64;
+            for (t_j, &a_j) in t.iter_mut().zip(a) {
+                let x = u64::from(*t_j) + u64::from(a_j) * u64::from(b_i) + carry;
+                *t_j = x as u32;
+                carry = x >> 32;
+            }
+            let x = u64::from(t[s]) + carry;
+            t[s] = x as u32;
+            t[s + 1] = (x >> 32) as u32;
+
+            let m = u64::from(t[0].wrapping_mul(self.n0_inv));
+            let mut carry = (u64::from(t[0]) + m * u64::from(n[0])) >> 32;
+            for (j, &n_j) in n.iter().enumerate().skip(1) {
+                let x = u64::from(t[j]) + m * u64::from(n_j) + carry;
+                t[j - 1] = x as u32;
+                carry = x >> 32;
+            }
+            let x = u64::from(t[s]) + carry;
+            t[s - 1] = x as u32;
+            t[s] = t[s + 1] + (x >> 32) as u32;
+        }
+
+        t.truncate(s);
+        t
+    }
+
+    // a in Montgomery form as digits, for any integer a
+    fn montgomery_digits(&self, a: &BigInt) -> Vec<u32> {
+        digits(&self.to_montgomery(a), self.n_digits.len())
+    }
+
+    // Back from digits below 2n in Montgomery form to a mod n
+    fn reduce_digits(&self, a: &[u32]) -> BigInt {
+        self.reduce(&BigInt::from_slice(Sign::Plus, a))
+    }
+
+    // aR mod n for any integer a
+    pub fn to_montgomery(&self, a: &BigInt) -> BigInt {
+        self.reduce(&(a.mod_floor(&self.n) * &self.r2))
+    }
+
+    // Back from aR mod n to a mod n
+    pub fn from_montgomery(&self, a: &BigInt) -> BigInt {
+        self.reduce(a)
+    }
+
+    // Product of two numbers already in Montgomery form, the result stays in Montgomery form
+    pub fn mul(&self, a: &BigInt, b: &BigInt) -> BigInt {
+        self.reduce(&(a * b))
+    }
+
+    // base^exponent mod n for a non-negative exponent, taking and returning ordinary numbers.
+    // Uses left-to-right sliding windows over a table of the odd powers of base.
+    // Ref: Handbook of Applied Cryptography, Algorithm 14.85
+    pub fn pow(&self, base: &BigInt, exponent: &BigInt) -> BigInt {
+        let bits: Vec<bool> = exponent.to_str_radix(2).chars().map(|c| c == '1').collect();
+        let window = window_size(bits.len());
+
+        // odd_powers[i] = base^(2i + 1) in Montgomery form
+        let base_m = self.montgomery_digits(base);
+        let base_squared = self.mul_digits(&base_m, &base_m);
+        let mut odd_powers = vec![base_m];
+        for i in 1..(1 << (window - 1)) {
+            let next = self.mul_digits(&odd_powers[i - 1], &base_squared);
+            odd_powers.push(next);
+        }
+
+        let mut result = self.montgomery_digits(&BigInt::one());
+        let mut i = 0;
+
+        while i < bits.len() {
+            if !bits[i] {
+                result = self.mul_digits(&result, &result);
+                i += 1;
+                continue;
+            }
+
+            // Longest window of at most `window` bits starting here that ends in a one
+            let mut end = (i + window).min(bits.len());
+            while !bits[end - 1] {
+                end -= 1;
+            }
+
+            let value = bits[i..end]
+                .iter()
+                .fold(0, |acc, &bit| (acc << 1) | bit as usize);
+
+            for _ in i..end {
+                result = self.mul_digits(&result, &result);
+            }
+            result = self.mul_digits(&result, &odd_powers[value >> 1]);
+
+            i = end;
+        }
+
+        self.reduce_digits(&result)
+    }
+}
+
+// Exponentiation for secret exponents: the sequence of multiplications doesn't depend on the
+// exponent bits, the exponent is padded to exponent_bits (pass the size of the modulus, not of the
+// exponent) and REDC never takes its data-dependent subtraction.
+//
+// Note that this only removes the timing differences the algorithms themselves introduce.
+// num-bigint trims leading zero digits and makes no constant-time promises, so this hardens the
+// demo against the classic attacks but is not a substitute for a vetted constant-time library.
+impl MontgomeryContext {
+    // Fixed 4-bit windows, multiplying by a table entry even when the window is zero
+    // Ref: Handbook of Applied Cryptography, Algorithm 14.82
+    pub fn pow_fixed_window(
+        &self,
+        base: &BigInt,
+        exponent: &BigInt,
+        exponent_bits: usize,
+    ) -> BigInt {
+        self.pow_fixed_window_counted(
+            base,
+            exponent,
+            exponent_bits,
+            &mut OperationCount::default(),
+        )
+    }
+
+    // pow_fixed_window, adding every Montgomery multiplication it does to count. The conversions in
+    // and out of Montgomery form are multiplications too (by R^2 mod n and by 1), so they skip the
+    // final subtraction like the rest. The only one left is at the very end, where the lazy result
+    // can be n itself when base^exponent = 0 (mod n).
+    pub(crate) fn pow_fixed_window_counted(
+        &self,
+        base: &BigInt,
+        exponent: &BigInt,
+        exponent_bits: usize,
+        count: &mut OperationCount,
+    ) -> BigInt {
+        let windows = fixed_windows(exponent, exponent_bits);
+        let digit_count = self.n_digits.len();
+        let r2 = digits(&self.r2, digit_count);
+        let one = digits(&BigInt::one(), digit_count);
+
+        // table[i] = base^i in Montgomery form, all below 2n
+        let base_m = self.mul_counted(&digits(&base.mod_floor(&self.n), digit_count), &r2, count);
+        let mut table = vec![self.mul_counted(&one, &r2, count), base_m.clone()];
+        for i in 2..16 {
+            let next = self.mul_counted(&table[i - 1], &base_m, count);
+            table.push(next);
+        }
+
+        let mut result = table[0].clone();
+
+        for window in windows {
+            for _ in 0..4 {
+                result = self.mul_counted(&result, &result, count);
+            }
+            result = self.mul_counted(&result, &table[window], count);
+        }
+
+        let result = BigInt::from_slice(Sign::Plus, &self.mul_counted(&result, &one, count));
+        if result >= self.n {
+            count.extra_reductions += 1;
+            result - &self.n
+        } else {
+            result
+        }
+    }
+
+    fn mul_counted(&self, a: &[u32], b: &[u32], count: &mut OperationCount) -> Vec<u32> {
+        count.multiplications += 1;
+        self.mul_digits(a, b)
+    }
+}
+
+// What an exponentiation did, for the timing lab: how many Montgomery multiplications, and how
+// many final subtractions, the data-dependent step timing attacks look for
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct OperationCount {
+    pub(crate) multiplications: u64,
+    pub(crate) extra_reductions: u64,
+}
+
+// The lowest `count` 32-bit digits of a non-negative a, least significant first
+fn digits(a: &BigInt, count: usize) -> Vec<u32> {
+    let (_, bytes) = a.to_bytes_le();
+    let mut digits = vec![0u32; count];
+
+    for (i, &byte) in bytes.iter().take(4 * count).enumerate() {
+        digits[i / 4] |= u32::from(byte) << (8 * (i % 4));
+    }
+
+    digits
+}
+
+// The bits of exponent from the most significant down, left-padded with zeros to exponent_bits
+fn padded_bits(exponent: &BigInt, exponent_bits: usize) -> Vec<bool> {
+    let digits = exponent.to_str_radix(2);
+    let padding = exponent_bits.saturating_sub(digits.len());
+
+    (0..padding)
+        .map(|_| false)
+        .chain(digits.chars().map(|c| c == '1'))
+        .collect()
+}
+
+// The padded exponent split into 4-bit windows from the most significant down
+fn fixed_windows(exponent: &BigInt, exponent_bits: usize) -> Vec<usize> {
+    let bits = padded_bits(exponent, (exponent_bits + 3) & !3);
+    let padding = (4 - bits.len() % 4) % 4;
+
+    let bits: Vec<bool> = (0..padding).map(|_| false).chain(bits).collect();
+
+    bits.chunks(4)
+        .map(|chunk| chunk.iter().fold(0, |acc, &bit| (acc << 1) | bit as usize))
+        .collect()
+}
+
+// Window sizes that minimise the number of multiplications for a given exponent length
+fn window_size(exponent_bits: usize) -> usize {
+    match exponent_bits {
+        0..=24 => 1,
+        25..=80 => 3,
+        81..=240 => 4,
+        241..=672 => 5,
+        _ => 6,
+    }
+}
+
+#[cfg(test)]
+mod test_montgomery_context {
+    use super::*;
+    use crypto_math::string_to_number;
+
+    fn context(n: &str) -> MontgomeryContext {
+        MontgomeryContext::new(&string_to_number(n)).unwrap()
+    }
+
+    #[test]
+    fn rejects_even_moduli() {
+        assert!(MontgomeryContext::new(&string_to_number("10")).is_none());
+        assert!(MontgomeryContext::new(&string_to_number("1")).is_none());
+    }
+
+    #[test]
+    fn round_trip() {
+        let ctx = context("1000000007");
+        for a in &["0", "1", "2", "999999999", "1000000006"] {
+            let a_num = string_to_number(a);
+            assert_eq!(ctx.from_montgomery(&ctx.to_montgomery(&a_num)), a_num);
+        }
+    }
+
+    #[test]
+    fn negative_and_oversized_inputs() {
+        let ctx = context("97");
+        let a = ctx.to_montgomery(&string_to_number("-1"));
+        assert_eq!(ctx.from_montgomery(&a), string_to_number("96"));
+        assert_eq!(
+            ctx.pow(&string_to_number("1000"), &string_to_number("3")),
+            string_to_number("1000").modpow(&string_to_number("3"), &string_to_number("97"))
+        );
+    }
+
+    #[test]
+    fn miniscule() {
+        let ctx = context("7");
+        assert_eq!(
+            ctx.pow(&string_to_number("3"), &string_to_number("0")),
+            string_to_number("1")
+        );
+        assert_eq!(
+            ctx.pow(&string_to_number("3"), &string_to_number("6")),
+            string_to_number("1")
+        );
+        assert_eq!(
+            ctx.pow(&string_to_number("3"), &string_to_number("5")),
+            string_to_number("5")
+        );
+    }
+
+    #[test]
+    fn matches_modpow() {
+        let n = string_to_number(
+            "114966634058035650214830057934576985759221289345518872320663318154359625194089",
+        );
+        let ctx = MontgomeryContext::new(&n).unwrap();
+        let base = string_to_number("123456789123456789123456789");
+
+        let mut exponent = string_to_number("1");
+        for _ in 0..300 {
+            assert_eq!(ctx.pow(&base, &exponent), base.modpow(&exponent, &n));
+            exponent = exponent * 7 + 3;
+        }
+    }
+
+    #[test]
+    fn digit_boundaries() {
+        // Moduli filling their top digit exactly, and ones just past it
+        for bits in &[30, 31, 32, 33, 62, 63, 64, 65, 127, 128, 129] {
+            let n = (BigInt::one() << *bits) - BigInt::from(1 + 2 * *bits);
+            let ctx = MontgomeryContext::new(&n).unwrap();
+            let base = &n - BigInt::from(2);
+            let exponent = &n - BigInt::one();
+
+            assert_eq!(ctx.pow(&base, &exponent), base.modpow(&exponent, &n));
+            assert_eq!(
+                ctx.pow_fixed_window(&base, &exponent, *bits),
+                base.modpow(&exponent, &n)
+            );
+        }
+    }
+
+    #[test]
+    fn fixed_window_matches_modpow() {
+        let n = string_to_number(
+            "114966634058035650214830057934576985759221289345518872320663318154359625194089",
+        );
+        let ctx = MontgomeryContext::new(&n).unwrap();
+        let base = string_to_number("123456789123456789123456789");
+
+        let mut exponent = string_to_number("0");
+        for _ in 0..300 {
+            assert_eq!(
+                ctx.pow_fixed_window(&base, &exponent, n.bits()),
+                base.modpow(&exponent, &n)
+            );
+            exponent = exponent * 7 + 3;
+        }
+    }
+
+    #[test]
+    fn fixed_window_handles_small_moduli() {
+        let ctx = context("7");
+        for e in 0..20 {
+            let e_num = BigInt::from(e);
+            let expected = string_to_number("3").modpow(&e_num, &string_to_number("7"));
+            assert_eq!(
+                ctx.pow_fixed_window(&string_to_number("3"), &e_num, 3),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn fixed_window_count_is_independent_of_operands() {
+        let n = string_to_number("3233");
+        let ctx = MontgomeryContext::new(&n).unwrap();
+
+        for (base, exponent) in &[("0", "0"), ("2", "1"), ("3232", "413"), ("1234", "4095")] {
+            let mut count = OperationCount::default();
+            ctx.pow_fixed_window_counted(
+                &string_to_number(base),
+                &string_to_number(exponent),
+                n.bits(),
+                &mut count,
+            );
+
+            // Two conversions in, 14 for the table, 5 for each of the three windows, one out
+            assert_eq!(
+                count,
+                OperationCount {
+                    multiplications: 32,
+                    extra_reductions: 0,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn padding_is_independent_of_exponent() {
+        // The same number of windows whether the exponent is tiny or uses every bit
+        assert_eq!(fixed_windows(&BigInt::from(1), 256).len(), 64);
+        assert_eq!(fixed_windows(&(BigInt::one() << 255), 256).len(), 64);
+        assert_eq!(
+            padded_bits(&BigInt::from(5), 8),
+            vec![false, false, false, false, false, true, false, true]
+        );
+    }
+
+    #[test]
+    fn mul_stays_in_montgomery_form() {
+        let ctx = context("1000000007");
+        let a = string_to_number("123456789");
+        let b = string_to_number("987654321");
+
+        let product = ctx.mul(&ctx.to_montgomery(&a), &ctx.to_montgomery(&b));
+        assert_eq!(
+            ctx.from_montgomery(&product),
+            (&a * &b) % string_to_number("1000000007")
+        );
+    }
+}
@@ -0,0 +1,1256 @@
+// Number theory over BigInt shared by the primality tests and the attacks: reduction into [0, m),
+// integer roots, the Jacobi symbol, factorisation and the functions built on it, primitive roots,
+// the Chinese remainder theorem and congruence solving. Functions taking and returning strings are
+// the public interface, the _num versions underneath work on BigInt for the rest of the crate.
+
+use crypto_math::{
+    extended_gcd, mod_inverse, number_to_string, string_to_number, ONE, SMALL_PRIMES, TWO, ZERO,
+};
+use montgomery::MontgomeryContext;
+use num::{
+    bigint::{BigInt, ToBigInt},
+    pow, Integer, Signed,
+};
+use pseudoprime::U64_BASES;
+
+// Always returns a value in [0, m), unlike % which keeps the sign of a
+pub(crate) fn modulo(a: &BigInt, m: &BigInt) -> BigInt {
+    let r = a % m;
+    if r < *ZERO {
+        r + m
+    } else {
+        r
+    }
+}
+
+// Floor of the square root by Newton's method
+pub(crate) fn bigint_sqrt(n: &BigInt) -> BigInt {
+    if *n < *TWO {
+        return n.clone();
+    }
+
+    // Newton's method only ever decreases from an overestimate, 2^(bits / 2 + 1) is a close one
+    let mut x: BigInt = &*ONE << (n.bits() / 2 + 1);
+    let mut y: BigInt = (&x + n / &x) / &*TWO;
+
+    while y < x {
+        x = y;
+        y = (&x + n / &x) / &*TWO;
+    }
+
+    x
+}
+
+// Based on the algorithm in: https://en.wikipedia.org/wiki/Jacobi_symbol#Calculating_the_Jacobi_symbol
+pub(crate) fn jacobi(a: &BigInt, n: &BigInt) -> i32 {
+    let three = string_to_number("3");
+    let four = string_to_number("4");
+    let five = string_to_number("5");
+    let eight = string_to_number("8");
+
+    let mut a = modulo(a, n);
+    let mut n = n.clone();
+    let mut result = 1;
+
+    while a != *ZERO {
+        while &a % &*TWO == *ZERO {
+            a /= &*TWO;
+            let r = &n % &eight;
+            if r == three || r == five {
+                result = -result;
+            }
+        }
+
+        ::std::mem::swap(&mut a, &mut n);
+
+        if &a % &four == three && &n % &four == three {
+            result = -result;
+        }
+
+        a = &a % &n;
+    }
+
+    if n == *ONE {
+        result
+    } else {
+        0
+    }
+}
+
+// The Jacobi symbol (a/n) for odd positive n, which is the Legendre symbol when n is prime
+pub fn jacobi_symbol(a: &str, n: &str) -> i32 {
+    let n_num = string_to_number(n);
+    assert!(
+        n_num > *ZERO && n_num.is_odd(),
+        "the Jacobi symbol is only defined for odd positive n"
+    );
+
+    jacobi(&string_to_number(a), &n_num)
+}
+
+#[cfg(test)]
+mod test_jacobi_symbol {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(jacobi_symbol("1", "3"), 1);
+        assert_eq!(jacobi_symbol("2", "3"), -1);
+        assert_eq!(jacobi_symbol("3", "3"), 0);
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(jacobi_symbol("1001", "9907"), -1);
+        assert_eq!(jacobi_symbol("19", "45"), 1);
+        assert_eq!(jacobi_symbol("8", "21"), -1);
+        assert_eq!(jacobi_symbol("5", "21"), 1);
+    }
+
+    #[test]
+    fn negative() {
+        assert_eq!(jacobi_symbol("-1", "5"), 1);
+        assert_eq!(jacobi_symbol("-1", "7"), -1);
+    }
+
+    #[test]
+    #[should_panic(expected = "odd positive n")]
+    fn zero_modulus() {
+        jacobi_symbol("1", "0");
+    }
+
+    #[test]
+    #[should_panic(expected = "odd positive n")]
+    fn even_modulus() {
+        jacobi_symbol("3", "10");
+    }
+
+    #[test]
+    #[should_panic(expected = "odd positive n")]
+    fn negative_modulus() {
+        jacobi_symbol("3", "-7");
+    }
+
+    #[test]
+    fn matches_euler_criterion() {
+        let p = string_to_number("1000000007");
+        let exponent = (&p - &*ONE) / &*TWO;
+
+        for a in 1..200 {
+            let a_num = a.to_bigint().unwrap();
+            let euler = a_num.modpow(&exponent, &p);
+            let expected = if euler == *ONE { 1 } else { -1 };
+            assert_eq!(jacobi(&a_num, &p), expected);
+        }
+    }
+}
+
+// Miller–Rabin with the first twelve primes as bases. This is deterministic below
+// psi_12 = 318665857834031151167461 (about 3.2 * 10^23) and doesn't need a seed, which is what the
+// factoring and group order code below wants.
+// Ref: https://oeis.org/A014233
+fn is_probable_prime(n: &BigInt) -> bool {
+    if *n < *TWO {
+        return false;
+    }
+
+    for &p in SMALL_PRIMES.iter() {
+        let p_num = p.to_bigint().unwrap();
+        if *n == p_num {
+            return true;
+        }
+        if n % &p_num == *ZERO {
+            return false;
+        }
+    }
+
+    let n_minus_one = n - &*ONE;
+    let mut d = n_minus_one.clone();
+    let mut s = 0;
+
+    while &d % &*TWO == *ZERO {
+        d /= &*TWO;
+        s += 1;
+    }
+
+    let context = MontgomeryContext::new(n).unwrap();
+    let one_m = context.to_montgomery(&ONE);
+    let n_minus_one_m = context.to_montgomery(&n_minus_one);
+
+    'bases: for &a in U64_BASES {
+        let mut x = context.to_montgomery(&context.pow(&a.to_bigint().unwrap(), &d));
+
+        if x == one_m || x == n_minus_one_m {
+            continue;
+        }
+
+        for _ in 1..s {
+            x = context.mul(&x, &x);
+            if x == n_minus_one_m {
+                continue 'bases;
+            }
+        }
+
+        return false;
+    }
+
+    true
+}
+
+// Pollard's rho with Brent's cycle detection, returns a non-trivial factor of the odd composite n.
+// Every step of the walk is charged to budget, and None once it runs out.
+// Ref: https://maths-people.anu.edu.au/~brent/pd/rpb051i.pdf
+fn pollard_brent(n: &BigInt, budget: &mut u64) -> Option<BigInt> {
+    let mut c = ONE.clone();
+
+    loop {
+        let f = |x: &BigInt| (x * x + &c) % n;
+
+        let mut y = TWO.clone();
+        let mut x = y.clone();
+        let mut ys = y.clone();
+        let mut q = ONE.clone();
+        let mut g = ONE.clone();
+        let mut r: u64 = 1;
+
+        // Multiply up to this many differences together before taking a gcd
+        let m: u64 = 128;
+
+        while g == *ONE {
+            x = y.clone();
+            *budget = budget.checked_sub(r)?;
+            for _ in 0..r {
+                y = f(&y);
+            }
+
+            let mut k = 0;
+            while k < r && g == *ONE {
+                ys = y.clone();
+                let batch = m.min(r - k);
+                *budget = budget.checked_sub(batch)?;
+                for _ in 0..batch {
+                    y = f(&y);
+                    q = q * (&x - &y) % n;
+                }
+                g = q.gcd(n);
+                k += m;
+            }
+
+            r *= 2;
+        }
+
+        // The batched gcd overshot, step back one difference at a time
+        if g == *n {
+            loop {
+                *budget = budget.checked_sub(1)?;
+                ys = f(&ys);
+                g = (&x - &ys).gcd(n);
+                if g != *ONE {
+                    break;
+                }
+            }
+        }
+
+        if g != *n {
+            return Some(g);
+        }
+
+        c += &*ONE;
+    }
+}
+
+// factorize_num, giving up with None once rho has taken budget steps in total
+pub(crate) fn factorize_within_budget(n: &BigInt, budget: &mut u64) -> Option<Vec<(BigInt, u32)>> {
+    let mut factors: Vec<(BigInt, u32)> = Vec::new();
+    let mut remaining = n.abs();
+
+    if remaining < *TWO {
+        return Some(factors);
+    }
+
+    for &p in SMALL_PRIMES.iter() {
+        let p_num = p.to_bigint().unwrap();
+        let mut exponent = 0;
+
+        while &remaining % &p_num == *ZERO {
+            remaining /= &p_num;
+            exponent += 1;
+        }
+
+        if exponent > 0 {
+            factors.push((p_num, exponent));
+        }
+    }
+
+    // Whatever is left has no small factors, split it with rho until only primes remain
+    let mut to_split = vec![remaining];
+    while let Some(m) = to_split.pop() {
+        if m == *ONE {
+            continue;
+        }
+
+        if is_probable_prime(&m) {
+            match factors.iter_mut().find(|&&mut (ref p, _)| *p == m) {
+                Some(entry) => entry.1 += 1,
+                None => factors.push((m, 1)),
+            }
+            continue;
+        }
+
+        let d = pollard_brent(&m, budget)?;
+        to_split.push(&m / &d);
+        to_split.push(d);
+    }
+
+    factors.sort();
+    Some(factors)
+}
+
+pub(crate) fn factorize_num(n: &BigInt) -> Vec<(BigInt, u32)> {
+    // No factor rho can find takes anywhere near 2^64 steps
+    factorize_within_budget(n, &mut 18446744073709551615).unwrap()
+}
+
+// Prime factorisation of |n| as (prime, exponent) pairs in increasing order, using trial division
+// by the small primes and then Pollard's rho
+pub fn factorize(n: &str) -> Vec<(String, u32)> {
+    factorize_num(&string_to_number(n))
+        .iter()
+        .map(|&(ref p, e)| (number_to_string(p), e))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_factorize {
+    use super::*;
+
+    fn pairs(factors: &[(&str, u32)]) -> Vec<(String, u32)> {
+        factors.iter().map(|&(p, e)| (p.to_string(), e)).collect()
+    }
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(factorize("1"), pairs(&[]));
+        assert_eq!(factorize("2"), pairs(&[("2", 1)]));
+        assert_eq!(factorize("12"), pairs(&[("2", 2), ("3", 1)]));
+    }
+
+    #[test]
+    fn small() {
+        assert_eq!(factorize("-360"), pairs(&[("2", 3), ("3", 2), ("5", 1)]));
+        assert_eq!(
+            factorize("9624742921"),
+            pairs(&[("1171", 1), ("2341", 1), ("3511", 1)])
+        );
+    }
+
+    #[test]
+    fn medium() {
+        // 2^64 + 1
+        assert_eq!(
+            factorize("18446744073709551617"),
+            pairs(&[("274177", 1), ("67280421310721", 1)])
+        );
+    }
+
+    #[test]
+    fn large() {
+        // 2^67 - 1, Cole's famous factorisation
+        assert_eq!(
+            factorize("147573952589676412927"),
+            pairs(&[("193707721", 1), ("761838257287", 1)])
+        );
+    }
+
+    #[test]
+    fn repeated_large_factor() {
+        // 1000003^2 * 1000033
+        assert_eq!(
+            factorize("1000039000207000297"),
+            pairs(&[("1000003", 2), ("1000033", 1)])
+        );
+    }
+}
+
+fn euler_phi_num(n: &BigInt) -> BigInt {
+    factorize_num(n)
+        .iter()
+        .fold(ONE.clone(), |phi, &(ref p, e)| {
+            phi * pow(p.clone(), e as usize - 1) * (p - &*ONE)
+        })
+}
+
+// Euler's totient, the number of integers in [1, n] coprime to n
+pub fn euler_phi(n: &str) -> String {
+    number_to_string(&euler_phi_num(&string_to_number(n)))
+}
+
+#[cfg(test)]
+mod test_euler_phi {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(euler_phi("1"), "1");
+        assert_eq!(euler_phi("2"), "1");
+        assert_eq!(euler_phi("9"), "6");
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(euler_phi("36"), "12");
+        assert_eq!(euler_phi("97"), "96");
+        assert_eq!(euler_phi("561"), "320");
+    }
+
+    #[test]
+    fn large() {
+        // (193707721 - 1) * (761838257287 - 1)
+        assert_eq!(euler_phi("147573952589676412927"), "147573951827644447920");
+    }
+}
+
+fn carmichael_lambda_num(n: &BigInt) -> BigInt {
+    carmichael_lambda_within_budget(n, &mut 18446744073709551615).unwrap()
+}
+
+// carmichael_lambda_num, charging the factorisation of n to budget
+fn carmichael_lambda_within_budget(n: &BigInt, budget: &mut u64) -> Option<BigInt> {
+    let eight = string_to_number("8");
+
+    let lambda = factorize_within_budget(n, budget)?
+        .iter()
+        .map(|&(ref p, e)| {
+            let prime_power_phi = pow(p.clone(), e as usize - 1) * (p - &*ONE);
+
+            // The group of units mod 2^k is not cyclic for k >= 3
+            if *p == *TWO && pow(p.clone(), e as usize) >= eight {
+                prime_power_phi / &*TWO
+            } else {
+                prime_power_phi
+            }
+        })
+        .fold(ONE.clone(), |lambda, x| lambda.lcm(&x));
+
+    Some(lambda)
+}
+
+// The Carmichael function, the exponent of the group of units mod n: the smallest m with
+// a^m = 1 (mod n) for every a coprime to n
+pub fn carmichael_lambda(n: &str) -> String {
+    number_to_string(&carmichael_lambda_num(&string_to_number(n)))
+}
+
+#[cfg(test)]
+mod test_carmichael_lambda {
+    use super::*;
+    use crypto_math::lcm;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(carmichael_lambda("1"), "1");
+        assert_eq!(carmichael_lambda("2"), "1");
+        assert_eq!(carmichael_lambda("4"), "2");
+        assert_eq!(carmichael_lambda("8"), "2");
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(carmichael_lambda("15"), "4");
+        assert_eq!(carmichael_lambda("16"), "4");
+        assert_eq!(carmichael_lambda("97"), "96");
+        assert_eq!(carmichael_lambda("561"), "80");
+    }
+
+    #[test]
+    fn matches_keypair_lambda() {
+        // For n = pq this is lcm(p - 1, q - 1), as computed in Keypair::new
+        assert_eq!(
+            carmichael_lambda("147573952589676412927"),
+            lcm("193707720", "761838257286")
+        );
+    }
+}
+
+pub(crate) fn multiplicative_order_num(a: &BigInt, n: &BigInt) -> Option<BigInt> {
+    multiplicative_order_within_budget(a, n, &mut 18446744073709551615)
+}
+
+// multiplicative_order_num, charging the factorisations of n and lambda(n) to budget. Also None
+// once the budget runs out.
+pub(crate) fn multiplicative_order_within_budget(
+    a: &BigInt,
+    n: &BigInt,
+    budget: &mut u64,
+) -> Option<BigInt> {
+    if *n < *ONE || a.gcd(n) != *ONE {
+        return None;
+    }
+
+    let a = modulo(a, n);
+
+    // The order divides lambda(n), strip prime factors from lambda while a^(order / q) stays 1
+    let mut order = carmichael_lambda_within_budget(n, budget)?;
+
+    for (q, _) in factorize_within_budget(&order, budget)? {
+        while &order % &q == *ZERO && a.modpow(&(&order / &q), n) == ONE.clone() % n {
+            order /= &q;
+        }
+    }
+
+    Some(order)
+}
+
+// The smallest k > 0 with a^k = 1 (mod n), or None when a is not a unit mod n
+pub fn multiplicative_order(a: &str, n: &str) -> Option<String> {
+    multiplicative_order_num(&string_to_number(a), &string_to_number(n))
+        .map(|order| number_to_string(&order))
+}
+
+#[cfg(test)]
+mod test_multiplicative_order {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(multiplicative_order("2", "7"), Some("3".to_string()));
+        assert_eq!(multiplicative_order("3", "7"), Some("6".to_string()));
+        assert_eq!(multiplicative_order("1", "7"), Some("1".to_string()));
+        assert_eq!(multiplicative_order("5", "1"), Some("1".to_string()));
+    }
+
+    #[test]
+    fn not_a_unit() {
+        assert_eq!(multiplicative_order("6", "9"), None);
+        assert_eq!(multiplicative_order("0", "7"), None);
+    }
+
+    #[test]
+    fn composite_modulus() {
+        assert_eq!(multiplicative_order("2", "15"), Some("4".to_string()));
+        assert_eq!(multiplicative_order("3", "16"), Some("4".to_string()));
+        assert_eq!(multiplicative_order("-1", "10"), Some("2".to_string()));
+    }
+
+    #[test]
+    fn large() {
+        // 2 has order 61 modulo the Mersenne prime 2^61 - 1
+        assert_eq!(
+            multiplicative_order("2", "2305843009213693951"),
+            Some("61".to_string())
+        );
+    }
+}
+
+// Whether g generates the whole group of units mod n, i.e. has order phi(n)
+pub fn is_primitive_root(g: &str, p: &str) -> bool {
+    let g_num = string_to_number(g);
+    let p_num = string_to_number(p);
+
+    match multiplicative_order_num(&g_num, &p_num) {
+        Some(order) => order == euler_phi_num(&p_num),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test_is_primitive_root {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert!(is_primitive_root("3", "7"));
+        assert!(is_primitive_root("5", "7"));
+        assert!(!is_primitive_root("2", "7"));
+        assert!(!is_primitive_root("7", "7"));
+    }
+
+    #[test]
+    fn tiny() {
+        let roots: Vec<u32> = (1..23)
+            .filter(|g| is_primitive_root(&g.to_string(), "23"))
+            .collect();
+        assert_eq!(roots, vec![5, 7, 10, 11, 14, 15, 17, 19, 20, 21]);
+    }
+
+    #[test]
+    fn composite_modulus() {
+        assert!(is_primitive_root("2", "9"));
+        assert!(!is_primitive_root("2", "15"));
+    }
+
+    #[test]
+    fn large() {
+        // 37 is the smallest primitive root of the Mersenne prime 2^61 - 1
+        assert!(is_primitive_root("37", "2305843009213693951"));
+        assert!(!is_primitive_root("3", "2305843009213693951"));
+    }
+}
+
+// The smallest primitive root mod n, or None when the group of units mod n isn't cyclic. That
+// happens unless n is 2, 4, p^k or 2p^k for an odd prime p.
+pub fn find_primitive_root(p: &str) -> Option<String> {
+    let p_num = string_to_number(p);
+
+    if p_num < *TWO {
+        return None;
+    }
+
+    let phi = euler_phi_num(&p_num);
+    if carmichael_lambda_num(&p_num) != phi {
+        return None;
+    }
+
+    // g is a primitive root exactly when g^(phi / q) != 1 for every prime q dividing phi
+    let exponents: Vec<BigInt> = factorize_num(&phi).iter().map(|(q, _)| &phi / q).collect();
+
+    let mut g = ONE.clone();
+    while g < p_num {
+        if g.gcd(&p_num) == *ONE && exponents.iter().all(|e| g.modpow(e, &p_num) != *ONE) {
+            return Some(number_to_string(&g));
+        }
+        g += &*ONE;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test_find_primitive_root {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(find_primitive_root("2"), Some("1".to_string()));
+        assert_eq!(find_primitive_root("4"), Some("3".to_string()));
+        assert_eq!(find_primitive_root("7"), Some("3".to_string()));
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(find_primitive_root("23"), Some("5".to_string()));
+        assert_eq!(find_primitive_root("9"), Some("2".to_string()));
+        assert_eq!(find_primitive_root("50"), Some("3".to_string()));
+    }
+
+    #[test]
+    fn not_cyclic() {
+        assert_eq!(find_primitive_root("1"), None);
+        assert_eq!(find_primitive_root("8"), None);
+        assert_eq!(find_primitive_root("15"), None);
+    }
+
+    #[test]
+    fn large() {
+        assert_eq!(
+            find_primitive_root("2305843009213693951"),
+            Some("37".to_string())
+        );
+        assert_eq!(
+            find_primitive_root("170141183460469231731687303715884105727"),
+            Some("43".to_string())
+        );
+    }
+}
+
+// For a safe prime p = 2q + 1, a generator of the subgroup of order q (the quadratic residues).
+// Diffie–Hellman and friends work in this subgroup so that no small subgroups leak bits of the
+// secret exponent. Returns None when p is not a safe prime.
+pub fn find_subgroup_generator(p: &str) -> Option<String> {
+    let p_num = string_to_number(p);
+
+    if p_num < string_to_number("5") {
+        return None;
+    }
+
+    let q = (&p_num - &*ONE) / &*TWO;
+    if !is_probable_prime(&p_num) || !is_probable_prime(&q) {
+        return None;
+    }
+
+    // Any square other than 1 has order q, since q is prime
+    let mut h = TWO.clone();
+    loop {
+        let g = &h * &h % &p_num;
+        if g != *ONE {
+            return Some(number_to_string(&g));
+        }
+        h += &*ONE;
+    }
+}
+
+#[cfg(test)]
+mod test_find_subgroup_generator {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(find_subgroup_generator("5"), Some("4".to_string()));
+        assert_eq!(find_subgroup_generator("7"), Some("4".to_string()));
+    }
+
+    #[test]
+    fn tiny() {
+        let g = find_subgroup_generator("23").unwrap();
+        assert_eq!(multiplicative_order(&g, "23"), Some("11".to_string()));
+    }
+
+    #[test]
+    fn not_safe_prime() {
+        assert_eq!(find_subgroup_generator("13"), None);
+        assert_eq!(find_subgroup_generator("15"), None);
+        assert_eq!(find_subgroup_generator("3"), None);
+    }
+
+    #[test]
+    fn large() {
+        let p = "18446744073709550147";
+        let g = find_subgroup_generator(p).unwrap();
+        assert_eq!(
+            multiplicative_order(&g, p),
+            Some("9223372036854775073".to_string())
+        );
+    }
+}
+
+// Solves x = residues[i] (mod moduli[i]) for every i, the moduli don't need to be coprime. Returns
+// the solution in [0, lcm of the moduli), or None when the congruences contradict each other.
+pub(crate) fn chinese_remainder_num(
+    residues: &[BigInt],
+    moduli: &[BigInt],
+) -> Option<(BigInt, BigInt)> {
+    let mut x = ZERO.clone();
+    let mut m = ONE.clone();
+
+    for (r, n) in residues.iter().zip(moduli.iter()) {
+        // Solve x + m * t = r (mod n) for t
+        let d = m.gcd(n);
+        let difference = r - &x;
+
+        if &difference % &d != *ZERO {
+            return None;
+        }
+
+        let n_over_d = n / &d;
+        let (u, _) = extended_gcd(&number_to_string(&(&m / &d)), &number_to_string(&n_over_d));
+        let t = modulo(&(&difference / &d * string_to_number(&u)), &n_over_d);
+
+        x += &m * t;
+        m = &m * &n_over_d;
+        x = modulo(&x, &m);
+    }
+
+    Some((x, m))
+}
+
+pub fn chinese_remainder(residues: &[&str], moduli: &[&str]) -> Option<String> {
+    let residues: Vec<BigInt> = residues.iter().map(|r| string_to_number(r)).collect();
+    let moduli: Vec<BigInt> = moduli.iter().map(|n| string_to_number(n)).collect();
+
+    chinese_remainder_num(&residues, &moduli).map(|(x, _)| number_to_string(&x))
+}
+
+#[cfg(test)]
+mod test_chinese_remainder {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(
+            chinese_remainder(&["2", "3", "2"], &["3", "5", "7"]),
+            Some("23".to_string())
+        );
+    }
+
+    #[test]
+    fn non_coprime_moduli() {
+        assert_eq!(
+            chinese_remainder(&["3", "5"], &["4", "6"]),
+            Some("11".to_string())
+        );
+        assert_eq!(chinese_remainder(&["1", "2"], &["4", "6"]), None);
+    }
+
+    #[test]
+    fn large() {
+        assert_eq!(
+            chinese_remainder(&["123456789", "987654321"], &["1000000007", "1000000009"]),
+            Some("567901247098765490".to_string())
+        );
+    }
+}
+
+// Floor of the k-th root by Newton's method, x' = ((k - 1) x + n / x^(k - 1)) / k
+pub(crate) fn integer_root_num(n: &BigInt, k: u32) -> BigInt {
+    if *n < *TWO || k == 1 {
+        return n.clone();
+    }
+
+    let k_num = k.to_bigint().unwrap();
+    let next = |x: &BigInt| (x * (&k_num - &*ONE) + n / pow(x.clone(), k as usize - 1)) / &k_num;
+
+    // Start from 2^(bits / k + 1), which is never below the root
+    let mut x: BigInt = &*ONE << (n.bits() / k as usize + 1);
+    let mut y = next(&x);
+
+    while y < x {
+        x = y;
+        y = next(&x);
+    }
+
+    x
+}
+
+// Ref: https://en.wikipedia.org/wiki/Nth_root#Using_Newton's_method
+pub fn integer_root(n: &str, k: u32) -> String {
+    assert!(k > 0, "the 0th root is undefined");
+    let n_num = string_to_number(n);
+    assert!(
+        n_num >= *ZERO,
+        "only non-negative numbers have integer roots here"
+    );
+
+    number_to_string(&integer_root_num(&n_num, k))
+}
+
+#[cfg(test)]
+mod test_integer_root {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(integer_root("0", 3), "0");
+        assert_eq!(integer_root("1", 5), "1");
+        assert_eq!(integer_root("7", 1), "7");
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(integer_root("26", 3), "2");
+        assert_eq!(integer_root("27", 3), "3");
+        assert_eq!(integer_root("28", 3), "3");
+        assert_eq!(integer_root("1023", 10), "1");
+        assert_eq!(integer_root("1024", 10), "2");
+    }
+
+    #[test]
+    fn large() {
+        // (2^89 - 1)^3 and one less than it
+        let cube =
+            "237142198758023568227473376148421179634080284826471606646987303262222160213573631";
+        assert_eq!(integer_root(cube, 3), "618970019642690137449562111");
+        assert_eq!(
+            integer_root(&number_to_string(&(string_to_number(cube) - &*ONE)), 3),
+            "618970019642690137449562110"
+        );
+    }
+}
+
+fn solve_linear_congruence_num(
+    a: &BigInt,
+    b: &BigInt,
+    m: &BigInt,
+) -> Option<(BigInt, BigInt, BigInt)> {
+    if *m < *ONE {
+        return None;
+    }
+
+    let a = modulo(a, m);
+    let b = modulo(b, m);
+    let g = a.gcd(m);
+
+    if &b % &g != *ZERO {
+        return None;
+    }
+
+    // Dividing through by g leaves a unique solution modulo m / g
+    let step = m / &g;
+    let first = if step == *ONE {
+        ZERO.clone()
+    } else {
+        let inverse = mod_inverse(&number_to_string(&(&a / &g)), &number_to_string(&step)).unwrap();
+        modulo(&(&b / &g * string_to_number(&inverse)), &step)
+    };
+
+    Some((first, step, g))
+}
+
+// Every x in [0, m) with a x = b (mod m) as (x0, step, count), the solutions being x0 + k step for
+// k in [0, count). There are gcd(a, m) of them, step = m / gcd(a, m) apart, when gcd(a, m) divides
+// b and None otherwise, so a = 0 gives all of [0, m) or nothing.
+// Ref: https://en.wikipedia.org/wiki/Linear_congruence_theorem
+pub fn solve_linear_congruence(a: &str, b: &str, m: &str) -> Option<(String, String, String)> {
+    solve_linear_congruence_num(
+        &string_to_number(a),
+        &string_to_number(b),
+        &string_to_number(m),
+    )
+    .map(|(first, step, count)| {
+        (
+            number_to_string(&first),
+            number_to_string(&step),
+            number_to_string(&count),
+        )
+    })
+}
+
+#[cfg(test)]
+mod test_solve_linear_congruence {
+    use super::*;
+
+    // Every solution, written out
+    fn solutions(a: &str, b: &str, m: &str) -> Vec<String> {
+        match solve_linear_congruence(a, b, m) {
+            Some((first, step, count)) => num::range(ZERO.clone(), string_to_number(&count))
+                .map(|k| {
+                    number_to_string(&(string_to_number(&first) + string_to_number(&step) * k))
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(solutions("3", "1", "7"), vec!["5"]);
+        assert_eq!(solutions("0", "0", "3"), vec!["0", "1", "2"]);
+        assert_eq!(solve_linear_congruence("0", "1", "3"), None);
+        assert_eq!(solutions("5", "3", "1"), vec!["0"]);
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(solutions("14", "30", "100"), vec!["45", "95"]);
+        assert_eq!(solutions("-14", "-30", "100"), vec!["45", "95"]);
+        // 2x is always even mod 4, where mod_inverse just says None
+        assert_eq!(solve_linear_congruence("2", "1", "4"), None);
+        assert_eq!(mod_inverse("2", "4"), None);
+    }
+
+    #[test]
+    fn large() {
+        let a = "123456789012345678901234567890";
+        let m = "1000000000000000000000000000000";
+
+        // gcd(a, m) = 10
+        let (_, step, count) = solve_linear_congruence(a, "60", m).unwrap();
+        assert_eq!(step, "100000000000000000000000000000");
+        assert_eq!(count, "10");
+        for x in &solutions(a, "60", m) {
+            let product = string_to_number(a) * string_to_number(x);
+            assert_eq!(
+                modulo(&product, &string_to_number(m)),
+                string_to_number("60")
+            );
+        }
+    }
+
+    #[test]
+    fn many_solutions() {
+        // Every x works, without listing all 10^11 of them
+        assert_eq!(
+            solve_linear_congruence("0", "0", "100000000000"),
+            Some(("0".to_string(), "1".to_string(), "100000000000".to_string()))
+        );
+    }
+}
+
+// A square root of n modulo an odd prime p, for n a non-zero quadratic residue
+// Ref: https://en.wikipedia.org/wiki/Tonelli%E2%80%93Shanks_algorithm
+fn tonelli_shanks(n: &BigInt, p: &BigInt) -> BigInt {
+    let p_minus_one = p - &*ONE;
+    let (mut q, mut s) = (p_minus_one.clone(), 0);
+    while &q % &*TWO == *ZERO {
+        q /= &*TWO;
+        s += 1;
+    }
+
+    let mut z = TWO.clone();
+    while jacobi(&z, p) != -1 {
+        z += &*ONE;
+    }
+
+    let mut m = s;
+    let mut c = z.modpow(&q, p);
+    let mut t = n.modpow(&q, p);
+    let mut r = n.modpow(&((&q + &*ONE) / &*TWO), p);
+
+    while t != *ONE {
+        // The least i with t^(2^i) = 1
+        let mut i = 0;
+        let mut t_power = t.clone();
+        while t_power != *ONE {
+            t_power = &t_power * &t_power % p;
+            i += 1;
+        }
+
+        let b = c.modpow(&(&*ONE << (m - i - 1)), p);
+        m = i;
+        c = &b * &b % p;
+        t = &t * &c % p;
+        r = &r * &b % p;
+    }
+
+    r
+}
+
+// Rho steps solve_quadratic_congruence spends factoring m, enough when every prime factor but the
+// largest is below about 2^36
+const QUADRATIC_FACTOR_BUDGET: u64 = 1 << 20;
+
+// Most roots solve_quadratic_congruence lists, or keeps along the way
+const QUADRATIC_MAX_ROOTS: usize = 1 << 16;
+
+// The solutions of a x = b (mod m) written out, None when there are more than QUADRATIC_MAX_ROOTS
+fn linear_roots(a: &BigInt, b: &BigInt, m: &BigInt) -> Option<Vec<BigInt>> {
+    let (first, step, count) = match solve_linear_congruence_num(a, b, m) {
+        Some(solutions) => solutions,
+        None => return Some(Vec::new()),
+    };
+    if count > BigInt::from(QUADRATIC_MAX_ROOTS) {
+        return None;
+    }
+
+    Some(
+        num::range(ZERO.clone(), count)
+            .map(|k| &first + &step * k)
+            .collect(),
+    )
+}
+
+// Roots of a x^2 + b x + c modulo the prime p, None when there are too many to list
+fn quadratic_roots_mod_prime(
+    a: &BigInt,
+    b: &BigInt,
+    c: &BigInt,
+    p: &BigInt,
+) -> Option<Vec<BigInt>> {
+    let f = |x: &BigInt| modulo(&((a * x + b) * x + c), p);
+
+    if *p == *TWO {
+        return Some(
+            vec![ZERO.clone(), ONE.clone()]
+                .into_iter()
+                .filter(|x| f(x) == *ZERO)
+                .collect(),
+        );
+    }
+
+    if modulo(a, p) == *ZERO {
+        return linear_roots(b, &-c, p);
+    }
+
+    // Completing the square: (2 a x + b)^2 = b^2 - 4 a c
+    let discriminant = modulo(&(b * b - a * c * 4u32), p);
+    let ys = if discriminant == *ZERO {
+        vec![ZERO.clone()]
+    } else if jacobi(&discriminant, p) == 1 {
+        let y = tonelli_shanks(&discriminant, p);
+        vec![p - &y, y]
+    } else {
+        Vec::new()
+    };
+
+    let two_a_inverse = string_to_number(
+        &mod_inverse(&number_to_string(&(a * 2u32)), &number_to_string(p)).unwrap(),
+    );
+    let mut roots: Vec<BigInt> = ys
+        .iter()
+        .map(|y| modulo(&((y - b) * &two_a_inverse), p))
+        .collect();
+    roots.sort();
+    roots.dedup();
+    Some(roots)
+}
+
+// Roots modulo p^k, lifted one power of p at a time. For a root r mod p^j, r + t p^j is a root
+// mod p^(j + 1) exactly when f(r) / p^j + t f'(r) = 0 (mod p), a linear congruence in t that has
+// one solution, none or all p of them.
+// Ref: https://en.wikipedia.org/wiki/Hensel%27s_lemma
+fn quadratic_roots_mod_prime_power(
+    a: &BigInt,
+    b: &BigInt,
+    c: &BigInt,
+    p: &BigInt,
+    k: u32,
+) -> Option<Vec<BigInt>> {
+    let mut roots = quadratic_roots_mod_prime(a, b, c, p)?;
+    let mut p_power = p.clone();
+
+    for _ in 1..k {
+        let mut lifted = Vec::new();
+        for r in &roots {
+            let value = (a * r + b) * r + c;
+            let derivative = a * r * 2u32 + b;
+
+            for t in linear_roots(&derivative, &-(value / &p_power), p)? {
+                lifted.push(r + t * &p_power);
+            }
+            if lifted.len() > QUADRATIC_MAX_ROOTS {
+                return None;
+            }
+        }
+
+        roots = lifted;
+        p_power = &p_power * p;
+    }
+
+    Some(roots)
+}
+
+// Every x in [0, m) with a x^2 + b x + c = 0 (mod m), in increasing order. m is factored, the
+// roots modulo each prime power come from completing the square and Hensel lifting, and the CRT
+// puts them back together. None for m < 1 as in solve_linear_congruence, when splitting m takes
+// rho more than QUADRATIC_FACTOR_BUDGET steps, as for an RSA modulus, or when there are more than
+// QUADRATIC_MAX_ROOTS roots, which can be as many as m itself when the polynomial vanishes modulo
+// its factors.
+pub fn solve_quadratic_congruence(a: &str, b: &str, c: &str, m: &str) -> Option<Vec<String>> {
+    let (a, b, c) = (
+        string_to_number(a),
+        string_to_number(b),
+        string_to_number(c),
+    );
+    let m = string_to_number(m);
+    if m < *ONE {
+        return None;
+    }
+
+    let mut roots = vec![ZERO.clone()];
+    let mut modulus = ONE.clone();
+
+    for (p, k) in factorize_within_budget(&m, &mut QUADRATIC_FACTOR_BUDGET.clone())? {
+        let p_power = pow(p.clone(), k as usize);
+        let local = quadratic_roots_mod_prime_power(&a, &b, &c, &p, k)?;
+        if roots.len() * local.len() > QUADRATIC_MAX_ROOTS {
+            return None;
+        }
+
+        roots = roots
+            .iter()
+            .flat_map(|x| {
+                let modulus = &modulus;
+                let p_power = &p_power;
+                local.iter().map(move |y| {
+                    let residues = [x.clone(), y.clone()];
+                    let moduli = [modulus.clone(), p_power.clone()];
+                    chinese_remainder_num(&residues, &moduli).unwrap().0
+                })
+            })
+            .collect();
+        modulus = &modulus * &p_power;
+    }
+
+    roots.sort();
+    Some(roots.iter().map(number_to_string).collect())
+}
+
+// The square roots of a modulo m, as solve_quadratic_congruence for x^2 - a
+pub fn sqrt_mod(a: &str, m: &str) -> Option<Vec<String>> {
+    let minus_a = number_to_string(&-string_to_number(a));
+    solve_quadratic_congruence("1", "0", &minus_a, m)
+}
+
+#[cfg(test)]
+mod test_solve_quadratic_congruence {
+    use super::*;
+
+    // Every root found by trying all x < m
+    fn brute_force(a: i64, b: i64, c: i64, m: i64) -> Vec<String> {
+        (0..m)
+            .filter(|x| ((a * x + b) * x + c) % m == 0)
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(sqrt_mod("2", "7").unwrap(), vec!["3", "4"]);
+        assert!(sqrt_mod("3", "7").unwrap().is_empty());
+        assert_eq!(sqrt_mod("0", "9").unwrap(), vec!["0", "3", "6"]);
+        assert_eq!(sqrt_mod("5", "1").unwrap(), vec!["0"]);
+    }
+
+    #[test]
+    fn invalid_modulus() {
+        assert_eq!(solve_quadratic_congruence("1", "0", "-1", "0"), None);
+        assert_eq!(solve_quadratic_congruence("1", "0", "-1", "-15"), None);
+        assert_eq!(sqrt_mod("1", "0"), None);
+        assert_eq!(solve_linear_congruence("1", "1", "0"), None);
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(sqrt_mod("1", "15").unwrap(), vec!["1", "4", "11", "14"]);
+        assert_eq!(sqrt_mod("1", "8").unwrap(), vec!["1", "3", "5", "7"]);
+        assert_eq!(sqrt_mod("4", "8").unwrap(), vec!["2", "6"]);
+        assert_eq!(
+            solve_quadratic_congruence("1", "1", "1", "91").unwrap(),
+            brute_force(1, 1, 1, 91)
+        );
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let polynomials = [
+            (1, 0, -1),
+            (2, 3, 1),
+            (3, 0, 6),
+            (5, 10, 5),
+            (0, 4, 2),
+            (6, 1, 9),
+        ];
+
+        for &(a, b, c) in polynomials.iter() {
+            for m in 1..200 {
+                assert_eq!(
+                    solve_quadratic_congruence(
+                        &a.to_string(),
+                        &b.to_string(),
+                        &c.to_string(),
+                        &m.to_string()
+                    )
+                    .unwrap(),
+                    brute_force(a, b, c, m),
+                    "{}x^2 + {}x + {} mod {}",
+                    a,
+                    b,
+                    c,
+                    m
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn large() {
+        // n = (10^9 + 7)(10^9 + 9), 123456789^2 has four square roots mod n
+        let n = "1000000016000000063";
+        let x = string_to_number("123456789");
+        let square = number_to_string(&(&x * &x));
+
+        let roots = sqrt_mod(&square, n).unwrap();
+        assert_eq!(roots.len(), 4);
+        assert!(roots.contains(&"123456789".to_string()));
+        for r in &roots {
+            let r = string_to_number(r);
+            assert_eq!(modulo(&(&r * &r), &string_to_number(n)), &x * &x);
+        }
+    }
+
+    #[test]
+    fn out_of_reach() {
+        // Two 64-bit primes, rho would need billions of steps to split them
+        assert_eq!(
+            sqrt_mod("4", "340282366920938460843936948965011886881"),
+            None
+        );
+        // x^2 = 0 mod 2^40 has 2^20 roots, and everything is a root mod 2^61 - 1
+        assert_eq!(sqrt_mod("0", "1099511627776"), None);
+        assert_eq!(
+            solve_quadratic_congruence("0", "0", "0", "2305843009213693951"),
+            None
+        );
+        // Still fine with a large modulus that factors easily
+        assert_eq!(sqrt_mod("9", "1099511627776").unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,420 @@
+use crypto_math::{mod_inverse, number_to_string, string_to_number};
+use num::{bigint::BigInt, Integer, One, Signed, Zero};
+
+// Polynomials with coefficients in Z/nZ. n doesn't have to be prime, so dividing by a leading
+// coefficient can fail, and every operation that needs to divide returns None when it does. For
+// an RSA modulus that only happens on a coefficient sharing a factor with n.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModPolynomial {
+    // Coefficients from the constant term up, in [0, n) and without trailing zeros
+    coefficients: Vec<BigInt>,
+    modulus: BigInt,
+}
+
+// a^-1 mod n as a BigInt in [0, n)
+fn inverse(a: &BigInt, n: &BigInt) -> Option<BigInt> {
+    mod_inverse(&number_to_string(a), &number_to_string(n))
+        .map(|u| string_to_number(&u).mod_floor(n))
+}
+
+impl ModPolynomial {
+    // Coefficients are given from the constant term up
+    pub fn new(coefficients: &[BigInt], modulus: &BigInt) -> ModPolynomial {
+        let mut polynomial = ModPolynomial {
+            coefficients: coefficients.iter().map(|c| c.mod_floor(modulus)).collect(),
+            modulus: modulus.clone(),
+        };
+        polynomial.trim();
+        polynomial
+    }
+
+    // The polynomial x^degree
+    pub fn monomial(degree: usize, modulus: &BigInt) -> ModPolynomial {
+        let mut coefficients = vec![BigInt::zero(); degree + 1];
+        coefficients[degree] = BigInt::one();
+        ModPolynomial::new(&coefficients, modulus)
+    }
+
+    pub fn constant(c: &BigInt, modulus: &BigInt) -> ModPolynomial {
+        ModPolynomial::new(::std::slice::from_ref(c), modulus)
+    }
+
+    pub fn coefficients(&self) -> &[BigInt] {
+        &self.coefficients
+    }
+
+    pub fn modulus(&self) -> &BigInt {
+        &self.modulus
+    }
+
+    // None for the zero polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    // Horner's rule
+    pub fn evaluate(&self, x: &BigInt) -> BigInt {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(BigInt::zero(), |acc, c| {
+                (acc * x + c).mod_floor(&self.modulus)
+            })
+    }
+
+    pub fn add(&self, other: &ModPolynomial) -> ModPolynomial {
+        self.combine(other, |a, b| a + b)
+    }
+
+    pub fn sub(&self, other: &ModPolynomial) -> ModPolynomial {
+        self.combine(other, |a, b| a - b)
+    }
+
+    // Schoolbook multiplication, the degrees used here are tiny
+    pub fn mul(&self, other: &ModPolynomial) -> ModPolynomial {
+        if self.is_zero() || other.is_zero() {
+            return ModPolynomial::new(&[], &self.modulus);
+        }
+
+        let mut product =
+            vec![BigInt::zero(); self.coefficients.len() + other.coefficients.len() - 1];
+        for (i, a) in self.coefficients.iter().enumerate() {
+            for (j, b) in other.coefficients.iter().enumerate() {
+                product[i + j] = (&product[i + j] + a * b) % &self.modulus;
+            }
+        }
+
+        ModPolynomial::new(&product, &self.modulus)
+    }
+
+    pub fn scale(&self, c: &BigInt) -> ModPolynomial {
+        let scaled: Vec<BigInt> = self.coefficients.iter().map(|a| a * c).collect();
+        ModPolynomial::new(&scaled, &self.modulus)
+    }
+
+    // Square and multiply
+    pub fn pow(&self, mut exponent: u32) -> ModPolynomial {
+        let mut result = ModPolynomial::constant(&BigInt::one(), &self.modulus);
+        let mut base = self.clone();
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.mul(&base);
+            }
+            base = base.mul(&base);
+            exponent >>= 1;
+        }
+
+        result
+    }
+
+    // Scales the leading coefficient to 1
+    pub fn monic(&self) -> Option<ModPolynomial> {
+        let leading = self.coefficients.last()?;
+        Some(self.scale(&inverse(leading, &self.modulus)?))
+    }
+
+    // Long division, self = quotient * divisor + remainder with deg(remainder) < deg(divisor)
+    pub fn div_rem(&self, divisor: &ModPolynomial) -> Option<(ModPolynomial, ModPolynomial)> {
+        let divisor_degree = divisor.degree()?;
+        let leading_inverse = inverse(&divisor.coefficients[divisor_degree], &self.modulus)?;
+
+        let mut remainder = self.coefficients.clone();
+        let quotient_length = (remainder.len() + 1).saturating_sub(divisor.coefficients.len());
+        let mut quotient = vec![BigInt::zero(); quotient_length];
+
+        for shift in (0..quotient_length).rev() {
+            let factor = &remainder[shift + divisor_degree] * &leading_inverse % &self.modulus;
+
+            for (i, d) in divisor.coefficients.iter().enumerate() {
+                remainder[shift + i] =
+                    (&remainder[shift + i] - &factor * d).mod_floor(&self.modulus);
+            }
+            quotient[shift] = factor;
+        }
+
+        Some((
+            ModPolynomial::new(&quotient, &self.modulus),
+            ModPolynomial::new(&remainder, &self.modulus),
+        ))
+    }
+
+    // Monic greatest common divisor by Euclid's algorithm
+    // Ref: https://en.wikipedia.org/wiki/Polynomial_greatest_common_divisor#Euclid's_algorithm
+    pub fn gcd(&self, other: &ModPolynomial) -> Option<ModPolynomial> {
+        let mut a = self.clone();
+        let mut b = other.clone();
+
+        while !b.is_zero() {
+            let (_, r) = a.div_rem(&b)?;
+            a = b;
+            b = r;
+        }
+
+        if a.is_zero() {
+            Some(a)
+        } else {
+            a.monic()
+        }
+    }
+}
+
+impl ModPolynomial {
+    fn trim(&mut self) {
+        let zero = BigInt::zero();
+        while self.coefficients.last() == Some(&zero) {
+            self.coefficients.pop();
+        }
+    }
+
+    fn combine<F: Fn(&BigInt, &BigInt) -> BigInt>(
+        &self,
+        other: &ModPolynomial,
+        f: F,
+    ) -> ModPolynomial {
+        let zero = BigInt::zero();
+        let length = self.coefficients.len().max(other.coefficients.len());
+
+        let combined: Vec<BigInt> = (0..length)
+            .map(|i| {
+                f(
+                    self.coefficients.get(i).unwrap_or(&zero),
+                    other.coefficients.get(i).unwrap_or(&zero),
+                )
+            })
+            .collect();
+
+        ModPolynomial::new(&combined, &self.modulus)
+    }
+}
+
+// Polynomials over the integers are plain coefficient slices, constant term first. Coppersmith's
+// method needs them because the lattice step works over Z, not Z/nZ.
+
+pub(crate) fn integer_multiply(a: &[BigInt], b: &[BigInt]) -> Vec<BigInt> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+
+    let mut product = vec![BigInt::zero(); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] = &product[i + j] + x * y;
+        }
+    }
+
+    product
+}
+
+pub(crate) fn integer_evaluate(coefficients: &[BigInt], x: &BigInt) -> BigInt {
+    coefficients
+        .iter()
+        .rev()
+        .fold(BigInt::zero(), |acc, c| acc * x + c)
+}
+
+fn integer_derivative(coefficients: &[BigInt]) -> Vec<BigInt> {
+    coefficients
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, c)| c * BigInt::from(i))
+        .collect()
+}
+
+// Integers r in [low, high] such that every real root of the polynomial in that range lies in
+// some [r, r + 1]. Between consecutive roots of the derivative the polynomial is monotonic, so a
+// sign change there pins down a root by bisection, and the derivative's own brackets are kept as
+// candidates to cover roots right next to a turning point.
+fn root_brackets(coefficients: &[BigInt], low: &BigInt, high: &BigInt) -> Vec<BigInt> {
+    if coefficients.len() < 2 {
+        return Vec::new();
+    }
+
+    let turning = root_brackets(&integer_derivative(coefficients), low, high);
+
+    let mut breakpoints = vec![low.clone(), high.clone()];
+    for r in &turning {
+        breakpoints.push(r.clone());
+        if r < high {
+            breakpoints.push(r + 1);
+        }
+    }
+    breakpoints.sort();
+    breakpoints.dedup();
+
+    let mut brackets = turning.clone();
+    for pair in breakpoints.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        let value_a = integer_evaluate(coefficients, a);
+        let value_b = integer_evaluate(coefficients, b);
+
+        if value_a.is_zero() {
+            brackets.push(a.clone());
+        }
+        if value_b.is_zero() {
+            brackets.push(b.clone());
+        }
+        if value_a.is_zero() || value_b.is_zero() || value_a.sign() == value_b.sign() {
+            continue;
+        }
+
+        // Keep the sign of value_a at left and value_b at right
+        let mut left = a.clone();
+        let mut right = b.clone();
+        while &right - &left > BigInt::one() {
+            let middle: BigInt = (&left + &right) >> 1;
+            let value = integer_evaluate(coefficients, &middle);
+
+            if value.is_zero() {
+                left = middle;
+                break;
+            } else if value.sign() == value_a.sign() {
+                left = middle;
+            } else {
+                right = middle;
+            }
+        }
+        brackets.push(left);
+    }
+
+    brackets.sort();
+    brackets.dedup();
+    brackets
+}
+
+// The integer roots x with |x| <= bound of a polynomial over Z
+pub fn integer_roots(coefficients: &[BigInt], bound: &BigInt) -> Vec<BigInt> {
+    let zero = BigInt::zero();
+    let mut coefficients = coefficients.to_vec();
+    while coefficients.last() == Some(&zero) {
+        coefficients.pop();
+    }
+
+    let low = -bound;
+    let mut roots: Vec<BigInt> = root_brackets(&coefficients, &low, bound)
+        .into_iter()
+        .flat_map(|r| {
+            let next = &r + 1;
+            vec![r, next]
+        })
+        .filter(|x| x.abs() <= *bound && integer_evaluate(&coefficients, x).is_zero())
+        .collect();
+
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+#[cfg(test)]
+mod test_integer_roots {
+    use super::*;
+
+    fn roots(coefficients: &[i64], bound: i64) -> Vec<BigInt> {
+        let coefficients: Vec<BigInt> = coefficients.iter().map(|&c| BigInt::from(c)).collect();
+        integer_roots(&coefficients, &BigInt::from(bound))
+    }
+
+    fn ints(xs: &[i64]) -> Vec<BigInt> {
+        xs.iter().map(|&x| BigInt::from(x)).collect()
+    }
+
+    #[test]
+    fn tiny() {
+        // (x - 2)(x + 3) = x^2 + x - 6
+        assert_eq!(roots(&[-6, 1, 1], 10), ints(&[-3, 2]));
+        assert_eq!(roots(&[-6, 1, 1], 2), ints(&[2]));
+        // x^2 - 2 has no integer roots
+        assert_eq!(roots(&[-2, 0, 1], 10), ints(&[]));
+    }
+
+    #[test]
+    fn repeated_and_close_roots() {
+        // (x - 5)^2 (x - 6) touches zero at 5 without a sign change
+        let p = integer_multiply(&ints(&[25, -10, 1]), &ints(&[-6, 1]));
+        assert_eq!(integer_roots(&p, &BigInt::from(100)), ints(&[5, 6]));
+    }
+
+    #[test]
+    fn large() {
+        // (x - 123456789012345)(x + 987654321)(3x^2 + 1)
+        let p = integer_multiply(
+            &integer_multiply(&ints(&[-123456789012345, 1]), &ints(&[987654321, 1])),
+            &ints(&[1, 0, 3]),
+        );
+        assert_eq!(
+            integer_roots(&p, &BigInt::from(1i64 << 50)),
+            ints(&[-987654321, 123456789012345])
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_mod_polynomial {
+    use super::*;
+
+    fn poly(coefficients: &[i64], n: i64) -> ModPolynomial {
+        let coefficients: Vec<BigInt> = coefficients.iter().map(|&c| BigInt::from(c)).collect();
+        ModPolynomial::new(&coefficients, &BigInt::from(n))
+    }
+
+    #[test]
+    fn reduces_and_trims() {
+        let p = poly(&[-1, 12, 24, 0], 12);
+        assert_eq!(p.coefficients(), &[BigInt::from(11)][..]);
+        assert_eq!(p.degree(), Some(0));
+        assert_eq!(poly(&[0, 0], 12).degree(), None);
+    }
+
+    #[test]
+    fn arithmetic() {
+        // (x + 1)(x + 2) = x^2 + 3x + 2
+        let product = poly(&[1, 1], 7).mul(&poly(&[2, 1], 7));
+        assert_eq!(product, poly(&[2, 3, 1], 7));
+        assert_eq!(product.sub(&poly(&[2, 3, 1], 7)), poly(&[], 7));
+        assert_eq!(product.add(&poly(&[5, 4], 7)), poly(&[0, 0, 1], 7));
+        assert_eq!(product.evaluate(&BigInt::from(3)), BigInt::from(6));
+    }
+
+    #[test]
+    fn pow() {
+        // (x + 1)^5 = x^5 + 1 (mod 5)
+        assert_eq!(poly(&[1, 1], 5).pow(5), poly(&[1, 0, 0, 0, 0, 1], 5));
+        assert_eq!(poly(&[1, 1], 5).pow(0), poly(&[1], 5));
+    }
+
+    #[test]
+    fn div_rem() {
+        let a = poly(&[3, 0, 2, 1], 11);
+        let b = poly(&[1, 3], 11);
+        let (q, r) = a.div_rem(&b).unwrap();
+
+        assert!(r.degree() < b.degree());
+        assert_eq!(q.mul(&b).add(&r), a);
+        assert_eq!(a.div_rem(&poly(&[], 11)), None);
+    }
+
+    #[test]
+    fn gcd() {
+        // (x - 2)(x - 3) and (x - 2)(x + 4) share x - 2
+        let a = poly(&[-2, 1], 101).mul(&poly(&[-3, 1], 101));
+        let b = poly(&[-2, 1], 101)
+            .mul(&poly(&[4, 1], 101))
+            .scale(&BigInt::from(5));
+        assert_eq!(a.gcd(&b), Some(poly(&[-2, 1], 101)));
+    }
+
+    #[test]
+    fn composite_modulus() {
+        // 3x + 1 can't be made monic mod 15
+        let a = poly(&[1, 0, 1], 15);
+        let b = poly(&[1, 3], 15);
+        assert_eq!(a.div_rem(&b), None);
+        assert_eq!(a.gcd(&b), None);
+        assert_eq!(poly(&[1, 2], 15).monic(), Some(poly(&[8, 1], 15)));
+    }
+}
@@ -0,0 +1,317 @@
+// Composites that pass primality tests. A Fermat liar a makes a^(n - 1) = 1 (mod n) hold for a
+// composite n, a strong liar passes a Miller–Rabin round too, and Carmichael numbers fool the Fermat
+// test for every base coprime to them. Everything here works on u64 with a deterministic
+// Miller–Rabin, so whole ranges can be searched in the browser.
+// Ref: https://en.wikipedia.org/wiki/Pseudoprime
+
+use num::Integer;
+
+// Deterministic Miller–Rabin bases for every 64-bit integer
+// Ref: https://miller-rabin.appspot.com/
+pub(crate) static U64_BASES: &[u64] = &[2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+fn mul_mod_u64(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+fn pow_mod_u64(mut base: u64, mut exponent: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    base %= m;
+
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod_u64(result, base, m);
+        }
+        base = mul_mod_u64(base, base, m);
+        exponent >>= 1;
+    }
+
+    result
+}
+
+// Writes n - 1 = d * 2^s with d odd
+fn split_u64(n: u64) -> (u64, u32) {
+    let s = (n - 1).trailing_zeros();
+    ((n - 1) >> s, s)
+}
+
+// A single round of Miller–Rabin for odd n > 2 with a base already reduced mod n
+fn strong_probable_prime_u64(n: u64, a: u64) -> bool {
+    let (d, s) = split_u64(n);
+    let mut x = pow_mod_u64(a, d, n);
+
+    if x == 1 || x == n - 1 {
+        return true;
+    }
+
+    for _ in 1..s {
+        x = mul_mod_u64(x, x, n);
+        if x == n - 1 {
+            return true;
+        }
+    }
+
+    false
+}
+
+pub(crate) fn is_prime_u64(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+
+    for &p in U64_BASES {
+        if Integer::is_multiple_of(&n, &p) {
+            return n == p;
+        }
+    }
+
+    U64_BASES.iter().all(|&a| strong_probable_prime_u64(n, a))
+}
+
+// Every base a in [1, n - 1] with a^(n - 1) = 1 (mod n) for composite n. A prime has no liars.
+pub fn fermat_liars(n: u64) -> Vec<u64> {
+    if n < 4 || is_prime_u64(n) {
+        return Vec::new();
+    }
+
+    (1..n).filter(|&a| pow_mod_u64(a, n - 1, n) == 1).collect()
+}
+
+#[cfg(test)]
+mod test_fermat_liars {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(fermat_liars(4), vec![1]);
+        assert_eq!(fermat_liars(7), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(fermat_liars(15), vec![1, 4, 11, 14]);
+    }
+
+    #[test]
+    fn carmichael() {
+        // Every base coprime to 561 lies, that's phi(561) = 320 of them
+        assert_eq!(fermat_liars(561).len(), 320);
+    }
+}
+
+// Every base a in [1, n - 1] that passes a Miller–Rabin round for odd composite n
+pub fn strong_liars(n: u64) -> Vec<u64> {
+    if n < 4 || n.is_even() || is_prime_u64(n) {
+        return Vec::new();
+    }
+
+    (1..n)
+        .filter(|&a| strong_probable_prime_u64(n, a))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_strong_liars {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(strong_liars(9), vec![1, 8]);
+        assert_eq!(strong_liars(13), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(strong_liars(25), vec![1, 7, 18, 24]);
+        assert_eq!(
+            strong_liars(91),
+            vec![1, 9, 10, 12, 16, 17, 22, 29, 38, 53, 62, 69, 74, 75, 79, 81, 82, 90]
+        );
+    }
+
+    #[test]
+    fn carmichael() {
+        // Far fewer strong liars than Fermat liars, which is why Miller–Rabin is needed
+        assert_eq!(strong_liars(561).len(), 10);
+    }
+}
+
+// Composite n with a^(n - 1) = 1 (mod n) for every one of the bases, bases divisible by n are
+// skipped
+pub fn is_fermat_pseudoprime(n: u64, bases: &[u64]) -> bool {
+    if n < 4 || is_prime_u64(n) {
+        return false;
+    }
+
+    bases
+        .iter()
+        .map(|&a| a % n)
+        .filter(|&a| a != 0)
+        .all(|a| pow_mod_u64(a, n - 1, n) == 1)
+}
+
+#[cfg(test)]
+mod test_is_fermat_pseudoprime {
+    use super::*;
+    use crypto_math::{is_prime, test_seed, BASES};
+
+    #[test]
+    fn tiny() {
+        assert!(is_fermat_pseudoprime(341, &[2]));
+        assert!(!is_fermat_pseudoprime(341, &[2, 3]));
+        assert!(!is_fermat_pseudoprime(337, &[2]));
+    }
+
+    #[test]
+    fn fools_the_fermat_check_in_is_prime() {
+        // 1171 * 2341 * 3511 is a Carmichael number with no factor below 1000, so it gets past
+        // both the trial division and the Fermat bases in is_prime, only Miller–Rabin catches it
+        let n = 9624742921;
+        let bases: Vec<u64> = BASES.iter().map(|&b| b as u64).collect();
+
+        assert!(is_fermat_pseudoprime(n, &bases));
+        assert!(!is_prime(&n.to_string(), test_seed()));
+    }
+}
+
+// Odd composite n that passes a Miller–Rabin round for every one of the bases, bases divisible by
+// n are skipped
+pub fn is_strong_pseudoprime(n: u64, bases: &[u64]) -> bool {
+    if n < 4 || n.is_even() || is_prime_u64(n) {
+        return false;
+    }
+
+    bases
+        .iter()
+        .map(|&a| a % n)
+        .filter(|&a| a != 0)
+        .all(|a| strong_probable_prime_u64(n, a))
+}
+
+#[cfg(test)]
+mod test_is_strong_pseudoprime {
+    use super::*;
+
+    #[test]
+    fn tiny() {
+        assert!(is_strong_pseudoprime(2047, &[2]));
+        assert!(!is_strong_pseudoprime(2047, &[2, 3]));
+        assert!(!is_strong_pseudoprime(341, &[2]));
+    }
+
+    #[test]
+    fn smallest_for_several_bases() {
+        assert!(is_strong_pseudoprime(1373653, &[2, 3]));
+        assert!(is_strong_pseudoprime(25326001, &[2, 3, 5]));
+        assert!(is_strong_pseudoprime(3215031751, &[2, 3, 5, 7]));
+        assert!(!is_strong_pseudoprime(3215031751, &[2, 3, 5, 7, 11]));
+    }
+}
+
+// Korselt's criterion: n is a Carmichael number if and only if it is composite, squarefree and
+// p - 1 divides n - 1 for every prime p dividing n.
+// Ref: https://en.wikipedia.org/wiki/Carmichael_number#Korselt's_criterion
+pub fn is_carmichael(n: u64) -> bool {
+    // Carmichael numbers are odd, so base 2 is a cheap filter before factoring
+    if n < 4 || n.is_even() || pow_mod_u64(2, n - 1, n) != 1 || is_prime_u64(n) {
+        return false;
+    }
+
+    let mut remaining = n;
+    let mut p = 3;
+
+    while p * p <= remaining {
+        if Integer::is_multiple_of(&remaining, &p) {
+            remaining /= p;
+            if Integer::is_multiple_of(&remaining, &p)
+                || !Integer::is_multiple_of(&(n - 1), &(p - 1))
+            {
+                return false;
+            }
+        }
+        p += 2;
+    }
+
+    remaining == 1 || Integer::is_multiple_of(&(n - 1), &(remaining - 1))
+}
+
+#[cfg(test)]
+mod test_is_carmichael {
+    use super::*;
+
+    #[test]
+    fn tiny() {
+        assert!(is_carmichael(561));
+        assert!(is_carmichael(1729));
+        assert!(!is_carmichael(341));
+        assert!(!is_carmichael(563));
+    }
+
+    #[test]
+    fn large() {
+        assert!(is_carmichael(9624742921));
+    }
+}
+
+// Carmichael numbers n with a <= n <= b
+pub fn carmichael_numbers_in_range(a: u64, b: u64) -> Vec<u64> {
+    (a..b.saturating_add(1))
+        .filter(|&n| is_carmichael(n))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_carmichael_numbers_in_range {
+    use super::*;
+
+    #[test]
+    fn small() {
+        assert_eq!(
+            carmichael_numbers_in_range(0, 100000),
+            vec![
+                561, 1105, 1729, 2465, 2821, 6601, 8911, 10585, 15841, 29341, 41041, 46657, 52633,
+                62745, 63973, 75361,
+            ]
+        );
+    }
+}
+
+// Fermat pseudoprimes to all the bases with a <= n <= b
+pub fn fermat_pseudoprimes_in_range(a: u64, b: u64, bases: &[u64]) -> Vec<u64> {
+    (a..b.saturating_add(1))
+        .filter(|&n| is_fermat_pseudoprime(n, bases))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_fermat_pseudoprimes_in_range {
+    use super::*;
+
+    #[test]
+    fn small() {
+        assert_eq!(
+            fermat_pseudoprimes_in_range(0, 3000, &[2]),
+            vec![341, 561, 645, 1105, 1387, 1729, 1905, 2047, 2465, 2701, 2821]
+        );
+    }
+}
+
+// Strong pseudoprimes to all the bases with a <= n <= b
+pub fn strong_pseudoprimes_in_range(a: u64, b: u64, bases: &[u64]) -> Vec<u64> {
+    (a..b.saturating_add(1))
+        .filter(|&n| is_strong_pseudoprime(n, bases))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_strong_pseudoprimes_in_range {
+    use super::*;
+
+    #[test]
+    fn small() {
+        assert_eq!(
+            strong_pseudoprimes_in_range(0, 20000, &[2]),
+            vec![2047, 3277, 4033, 4681, 8321, 15841]
+        );
+    }
+}
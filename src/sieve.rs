@@ -0,0 +1,276 @@
+// Prime enumeration over u64 ranges. A segmented Sieve of Eratosthenes crosses out multiples of
+// the primes up to sqrt(b) one cache-sized block at a time, so ranges far from zero only cost
+// memory for the block and the base primes. Past BASE_PRIME_BOUND^2 the base primes stop at
+// BASE_PRIME_BOUND and whatever survives the sieve is confirmed with the deterministic u64
+// Miller–Rabin, which is also all that ranges too narrow to be worth sieving get.
+// Ref: https://en.wikipedia.org/wiki/Sieve_of_Eratosthenes#Segmented_sieve
+
+use pseudoprime::is_prime_u64;
+
+// Size of each block the segmented sieve works through, small enough to stay in cache.
+const SEGMENT_SIZE: u64 = 32768;
+
+// Largest base prime the sieve uses. Sieving the base primes up to sqrt(u64::MAX) would take a
+// 4 GiB table (and overflow usize on wasm32), this keeps it at a megabyte while still leaving
+// only about one number in 25 for Miller–Rabin.
+const BASE_PRIME_BOUND: u64 = 1 << 20;
+
+// The sieve needs every base prime before it can start, so ranges narrower than
+// base bound / SIEVE_MIN_WIDTH_RATIO are tested one number at a time with Miller–Rabin instead.
+const SIEVE_MIN_WIDTH_RATIO: u64 = 1024;
+
+// Plain Sieve of Eratosthenes, only used to get the base primes for the segmented sieve
+fn simple_sieve(bound: u64) -> Vec<u64> {
+    if bound < 2 {
+        return Vec::new();
+    }
+
+    let size = bound as usize + 1;
+    let mut composite = vec![false; size];
+    let mut primes = Vec::new();
+
+    for i in 2..size {
+        if composite[i] {
+            continue;
+        }
+
+        primes.push(i as u64);
+
+        let mut multiple = i * i;
+        while multiple < size {
+            composite[multiple] = true;
+            multiple += i;
+        }
+    }
+
+    primes
+}
+
+fn integer_sqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+
+    let mut root = (n as f64).sqrt() as u64;
+
+    // The float estimate can be off by one either way. Comparing against n / root rather than
+    // squaring keeps this from overflowing near u64::MAX.
+    while root > n / root {
+        root -= 1;
+    }
+    loop {
+        let next = root + 1;
+        if next > n / next {
+            break;
+        }
+        root = next;
+    }
+
+    root
+}
+
+// Segmented Sieve of Eratosthenes over [a, b], calling found for every prime in increasing order.
+// Only the base primes up to min(sqrt(b), BASE_PRIME_BOUND) and one SEGMENT_SIZE block are ever
+// held in memory.
+fn segmented_sieve<F: FnMut(u64)>(a: u64, b: u64, mut found: F) {
+    let a = a.max(2);
+
+    if a > b {
+        return;
+    }
+
+    let base_bound = integer_sqrt(b).min(BASE_PRIME_BOUND);
+
+    if b - a < base_bound / SIEVE_MIN_WIDTH_RATIO {
+        for n in (a..=b).filter(|&n| is_prime_u64(n)) {
+            found(n);
+        }
+        return;
+    }
+
+    let base_primes = simple_sieve(base_bound);
+
+    // Without every prime up to sqrt(b) the survivors are only candidates
+    let complete = base_bound == integer_sqrt(b);
+
+    let mut low = a;
+    loop {
+        let high = b.min(low.saturating_add(SEGMENT_SIZE - 1));
+        let mut composite = vec![false; (high - low + 1) as usize];
+
+        for &p in &base_primes {
+            if p * p > high {
+                break;
+            }
+
+            let first_multiple = match low.checked_add((p - low % p) % p) {
+                Some(multiple) => multiple.max(p * p),
+                None => continue,
+            };
+
+            let mut multiple = first_multiple;
+            while multiple <= high {
+                composite[(multiple - low) as usize] = true;
+
+                // Stepping past u64::MAX ends the segment just like stepping past high
+                multiple = match multiple.checked_add(p) {
+                    Some(next) => next,
+                    None => break,
+                };
+            }
+        }
+
+        for (offset, _) in composite.iter().enumerate().filter(|&(_, c)| !c) {
+            let n = low + offset as u64;
+            if complete || is_prime_u64(n) {
+                found(n);
+            }
+        }
+
+        if high == b {
+            break;
+        }
+        low = high + 1;
+    }
+}
+
+// All primes p with p <= bound
+pub fn primes_up_to(bound: u64) -> Vec<u64> {
+    primes_in_range(2, bound)
+}
+
+#[cfg(test)]
+mod test_primes_up_to {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(primes_up_to(1), Vec::<u64>::new());
+        assert_eq!(primes_up_to(2), vec![2]);
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(primes_up_to(30), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+    }
+
+    #[test]
+    fn small() {
+        let primes = primes_up_to(1000);
+        assert_eq!(primes.len(), 168);
+        assert_eq!(primes.last(), Some(&997));
+    }
+
+    #[test]
+    fn medium() {
+        // Crosses several segments
+        let primes = primes_up_to(100000);
+        assert_eq!(primes.len(), 9592);
+        assert_eq!(primes.last(), Some(&99991));
+    }
+}
+
+// All primes p with a <= p <= b
+pub fn primes_in_range(a: u64, b: u64) -> Vec<u64> {
+    let mut primes = Vec::new();
+    segmented_sieve(a, b, |p| primes.push(p));
+    primes
+}
+
+#[cfg(test)]
+mod test_primes_in_range {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(primes_in_range(0, 10), vec![2, 3, 5, 7]);
+        assert_eq!(primes_in_range(10, 0), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(
+            primes_in_range(100, 130),
+            vec![101, 103, 107, 109, 113, 127]
+        );
+    }
+
+    #[test]
+    fn inclusive_bounds() {
+        assert_eq!(primes_in_range(7, 7), vec![7]);
+        assert_eq!(primes_in_range(89, 97), vec![89, 97]);
+    }
+
+    #[test]
+    fn large() {
+        let primes = primes_in_range(1000000000000, 1000000000100);
+        assert_eq!(
+            primes,
+            vec![1000000000039, 1000000000061, 1000000000063, 1000000000091]
+        );
+
+        // Wide enough to be sieved rather than tested one by one
+        let (a, b) = (1000000000000, 1000000100000);
+        let tested: Vec<u64> = (a..=b).filter(|&n| is_prime_u64(n)).collect();
+        assert_eq!(primes_in_range(a, b), tested);
+    }
+
+    #[test]
+    fn top_of_range() {
+        assert_eq!(
+            primes_in_range(18446744073709551515, 18446744073709551615),
+            vec![
+                18446744073709551521,
+                18446744073709551533,
+                18446744073709551557
+            ]
+        );
+        assert_eq!(integer_sqrt(18446744073709551615), 4294967295);
+    }
+
+    #[test]
+    fn wide_range_at_top() {
+        // 2^22 wide, so sieved with the capped base primes instead of tested one by one
+        let primes = primes_in_range(18446744073705357311, 18446744073709551615);
+        assert_eq!(primes.len(), 94461);
+        assert_eq!(primes[0], 18446744073705357317);
+        assert_eq!(
+            primes[primes.len() - 3..],
+            [
+                18446744073709551521,
+                18446744073709551533,
+                18446744073709551557
+            ]
+        );
+    }
+}
+
+// The prime-counting function, the number of primes p <= x
+pub fn prime_pi(x: u64) -> u64 {
+    let mut count = 0;
+    segmented_sieve(2, x, |_| count += 1);
+    count
+}
+
+#[cfg(test)]
+mod test_prime_pi {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(prime_pi(0), 0);
+        assert_eq!(prime_pi(2), 1);
+        assert_eq!(prime_pi(10), 4);
+    }
+
+    #[test]
+    fn small() {
+        assert_eq!(prime_pi(1000), 168);
+        assert_eq!(prime_pi(10000), 1229);
+    }
+
+    #[test]
+    fn medium() {
+        assert_eq!(prime_pi(1000000), 78498);
+    }
+}
@@ -0,0 +1,389 @@
+// Primality tests for numbers of a special form, which are deterministic and far faster than
+// Miller–Rabin on numbers this size: Lucas–Lehmer for Mersenne numbers 2^p - 1, Proth's theorem
+// for k 2^n + 1 and Pépin's test for Fermat numbers. Each runs a step at a time so the browser can
+// show how far along it is.
+
+use crypto_math::{number_to_string, string_to_number, ONE, TWO, ZERO};
+use num::{bigint::BigInt, pow};
+use number_theory::{bigint_sqrt, jacobi};
+use sieve::primes_in_range;
+use wasm_bindgen::prelude::*;
+
+// The Lucas–Lehmer test for the Mersenne number 2^p - 1, run a few iterations at a time so the
+// browser can report progress and stay responsive.
+// Ref: https://en.wikipedia.org/wiki/Lucas%E2%80%93Lehmer_primality_test
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct LucasLehmerTest {
+    // Exponent of the Mersenne number
+    p: u32,
+    // 2^p - 1
+    mersenne: BigInt,
+    // Current term of the sequence s_0 = 4, s_i+1 = s_i^2 - 2
+    s: BigInt,
+    iteration: u32,
+    total: u32,
+    result: Option<bool>,
+}
+
+#[wasm_bindgen]
+impl LucasLehmerTest {
+    pub fn new(p: u32) -> LucasLehmerTest {
+        let mersenne = pow(TWO.clone(), p as usize) - &*ONE;
+
+        // 2^p - 1 can only be prime when p is, and the test itself needs p to be an odd prime
+        let result = if p == 2 {
+            Some(true)
+        } else if primes_in_range(p as u64, p as u64).is_empty() {
+            Some(false)
+        } else {
+            None
+        };
+
+        LucasLehmerTest {
+            p,
+            mersenne,
+            s: string_to_number("4"),
+            iteration: 0,
+            total: if result.is_some() { 0 } else { p - 2 },
+            result,
+        }
+    }
+
+    // Runs at most iterations more steps and returns whether the test has finished
+    pub fn step(&mut self, iterations: u32) -> bool {
+        for _ in 0..iterations {
+            if self.iteration >= self.total {
+                break;
+            }
+
+            // Adding the modulus keeps s^2 - 2 non-negative when s < 2
+            let squared = &self.s * &self.s + &self.mersenne - &*TWO;
+            self.s = self.reduce(squared);
+            self.iteration += 1;
+        }
+
+        if self.result.is_none() && self.iteration >= self.total {
+            self.result = Some(self.s == *ZERO);
+        }
+
+        self.result.is_some()
+    }
+
+    pub fn iterations_done(&self) -> u32 {
+        self.iteration
+    }
+
+    pub fn iterations_total(&self) -> u32 {
+        self.total
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.result.is_some()
+    }
+
+    pub fn is_prime(&self) -> bool {
+        self.result == Some(true)
+    }
+}
+
+impl LucasLehmerTest {
+    // Reduction modulo 2^p - 1 without a division: x = (x mod 2^p) + (x >> p)
+    fn reduce(&self, mut x: BigInt) -> BigInt {
+        while x > self.mersenne {
+            x = (&x & &self.mersenne) + (&x >> self.p as usize);
+        }
+
+        if x == self.mersenne {
+            ZERO.clone()
+        } else {
+            x
+        }
+    }
+}
+
+// Whether the Mersenne number 2^p - 1 is prime
+pub fn lucas_lehmer(p: u32) -> bool {
+    let mut test = LucasLehmerTest::new(p);
+    let total = test.iterations_total();
+    test.step(total);
+    test.is_prime()
+}
+
+#[cfg(test)]
+mod test_lucas_lehmer {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert!(!lucas_lehmer(0));
+        assert!(!lucas_lehmer(1));
+        assert!(lucas_lehmer(2));
+        assert!(lucas_lehmer(3));
+        assert!(!lucas_lehmer(4));
+    }
+
+    #[test]
+    fn small() {
+        let exponents: Vec<u32> = (2..130).filter(|&p| lucas_lehmer(p)).collect();
+        assert_eq!(
+            exponents,
+            vec![2, 3, 5, 7, 13, 17, 19, 31, 61, 89, 107, 127]
+        );
+    }
+
+    #[test]
+    fn large() {
+        assert!(lucas_lehmer(1279));
+        assert!(!lucas_lehmer(1277));
+    }
+
+    #[test]
+    fn reports_progress() {
+        let mut test = LucasLehmerTest::new(521);
+        assert_eq!(test.iterations_total(), 519);
+
+        assert!(!test.step(100));
+        assert_eq!(test.iterations_done(), 100);
+        assert!(!test.is_finished());
+
+        while !test.step(100) {}
+        assert_eq!(test.iterations_done(), 519);
+        assert!(test.is_prime());
+    }
+}
+
+// Proth's theorem for N = k * 2^n + 1 with k odd and k < 2^n: N is prime if and only if
+// a^((N - 1) / 2) = -1 (mod N) for any quadratic non-residue a. The first power a^k is taken up
+// front and the remaining n - 1 squarings are the steps that get reported.
+// Ref: https://en.wikipedia.org/wiki/Proth%27s_theorem
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct ProthTest {
+    // k * 2^n + 1
+    number: BigInt,
+    // Current power of the non-residue
+    x: BigInt,
+    iteration: u32,
+    total: u32,
+    proth_number: bool,
+    result: Option<bool>,
+}
+
+#[wasm_bindgen]
+impl ProthTest {
+    pub fn new(k: &str, n: u32) -> ProthTest {
+        let mut k_num = string_to_number(k);
+        let mut n = n;
+
+        // Move factors of two from k into the exponent so k is odd
+        while k_num > *ZERO && &k_num % &*TWO == *ZERO {
+            k_num /= &*TWO;
+            n = n.checked_add(1).expect("the power of two has to fit a u32");
+        }
+
+        let number = &k_num * pow(TWO.clone(), n as usize) + &*ONE;
+        let proth_number = k_num > *ZERO && n > 0 && k_num < pow(TWO.clone(), n as usize);
+
+        let mut test = ProthTest {
+            number,
+            x: ZERO.clone(),
+            iteration: 0,
+            total: 0,
+            proth_number,
+            result: None,
+        };
+
+        if !proth_number {
+            test.result = Some(false);
+            return test;
+        }
+
+        // A perfect square has no quadratic non-residues to find
+        let root = bigint_sqrt(&test.number);
+        if &root * &root == test.number {
+            test.result = Some(false);
+            return test;
+        }
+
+        let mut a = TWO.clone();
+        loop {
+            match jacobi(&a, &test.number) {
+                -1 => break,
+                0 if a < test.number => {
+                    test.result = Some(false);
+                    return test;
+                }
+                _ => a += &*ONE,
+            }
+        }
+
+        test.x = a.modpow(&k_num, &test.number);
+        test.total = n - 1;
+        test
+    }
+
+    // Pépin's test for the Fermat number 2^(2^m) + 1, which is Proth's theorem with k = 1 where
+    // the non-residue found is always 3. The exponent 2^m has to fit a u32, so m is at most 31.
+    pub fn fermat(m: u32) -> ProthTest {
+        assert!(m < 32, "Fermat numbers past F31 are out of reach");
+        ProthTest::new("1", 1 << m)
+    }
+
+    // Runs at most iterations more squarings and returns whether the test has finished
+    pub fn step(&mut self, iterations: u32) -> bool {
+        for _ in 0..iterations {
+            if self.iteration >= self.total {
+                break;
+            }
+
+            self.x = &self.x * &self.x % &self.number;
+            self.iteration += 1;
+        }
+
+        if self.result.is_none() && self.iteration >= self.total {
+            self.result = Some(self.x == &self.number - &*ONE);
+        }
+
+        self.result.is_some()
+    }
+
+    pub fn number_display_wasm(&self) -> String {
+        number_to_string(&self.number)
+    }
+
+    pub fn is_proth_number(&self) -> bool {
+        self.proth_number
+    }
+
+    pub fn iterations_done(&self) -> u32 {
+        self.iteration
+    }
+
+    pub fn iterations_total(&self) -> u32 {
+        self.total
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.result.is_some()
+    }
+
+    pub fn is_prime(&self) -> bool {
+        self.result == Some(true)
+    }
+}
+
+// Whether k * 2^n + 1 is prime, or None when it isn't a Proth number (k >= 2^n)
+pub fn proth_test(k: &str, n: u32) -> Option<bool> {
+    let mut test = ProthTest::new(k, n);
+
+    if !test.is_proth_number() {
+        return None;
+    }
+
+    let total = test.iterations_total();
+    test.step(total);
+    Some(test.is_prime())
+}
+
+#[cfg(test)]
+mod test_proth_test {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(proth_test("1", 1), Some(true));
+        assert_eq!(proth_test("1", 2), Some(true));
+        assert_eq!(proth_test("1", 3), Some(false));
+        assert_eq!(proth_test("3", 2), Some(true));
+    }
+
+    #[test]
+    fn small() {
+        // Proth primes below 1000
+        let mut primes = Vec::new();
+        for n in 1..10 {
+            for k in (1..(1 << n)).filter(|k| k % 2 == 1) {
+                let number = k * (1 << n) + 1;
+                if number < 1000 && proth_test(&k.to_string(), n) == Some(true) {
+                    primes.push(number);
+                }
+            }
+        }
+        primes.sort();
+        assert_eq!(
+            primes,
+            vec![3, 5, 13, 17, 41, 97, 113, 193, 241, 257, 353, 449, 577, 641, 673, 769, 929]
+        );
+    }
+
+    #[test]
+    fn even_k_is_normalised() {
+        // 6 * 2^2 + 1 = 3 * 2^3 + 1 = 25
+        assert_eq!(proth_test("6", 2), Some(false));
+        // 2 * 2^3 + 1 = 1 * 2^4 + 1 = 17
+        assert_eq!(proth_test("2", 3), Some(true));
+    }
+
+    #[test]
+    fn not_a_proth_number() {
+        assert_eq!(proth_test("5", 2), None);
+        assert_eq!(proth_test("0", 5), None);
+        assert_eq!(proth_test("3", 0), None);
+    }
+
+    #[test]
+    fn large() {
+        // 3 * 2^189 + 1 is prime, 3 * 2^190 + 1 is not
+        assert_eq!(proth_test("3", 189), Some(true));
+        assert_eq!(proth_test("3", 190), Some(false));
+    }
+
+    #[test]
+    fn reports_progress() {
+        let mut test = ProthTest::new("3", 189);
+        assert_eq!(test.iterations_total(), 188);
+        assert!(!test.step(50));
+        assert_eq!(test.iterations_done(), 50);
+        while !test.step(50) {}
+        assert!(test.is_prime());
+    }
+}
+
+// Whether the Fermat number 2^(2^m) + 1 is prime
+pub fn pepin_test(m: u32) -> bool {
+    let mut test = ProthTest::fermat(m);
+    let total = test.iterations_total();
+    test.step(total);
+    test.is_prime()
+}
+
+#[cfg(test)]
+mod test_pepin_test {
+    use super::*;
+
+    #[test]
+    fn miniscule() {
+        assert!(pepin_test(0));
+        assert!(pepin_test(1));
+        assert!(pepin_test(2));
+    }
+
+    #[test]
+    fn small() {
+        assert!(pepin_test(3));
+        assert!(pepin_test(4));
+        assert!(!pepin_test(5));
+    }
+
+    #[test]
+    fn large() {
+        assert!(!pepin_test(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "past F31")]
+    fn out_of_reach() {
+        pepin_test(32);
+    }
+}
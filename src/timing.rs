@@ -0,0 +1,410 @@
+// A timing side-channel lab. A toy Keypair decrypts many chosen ciphertexts, either with the
+// textbook variable-time square-and-multiply Keypair::decrypt used before it switched to blinded
+// fixed-window exponentiation, or through Keypair::decrypt_number as it is today, and a simplified
+// Kocher attack tries to recover the private exponent from the timings alone.
+//
+// Wall-clock time is useless here: wasm has no clock without JavaScript and the noise would swamp
+// 32-bit keys anyway. Instead every decryption is charged a simulated cost, one unit per Montgomery
+// multiplication plus a penalty for each extra final subtraction. Those extra reductions are the
+// data-dependent step real attacks measure (Dhem et al., A Practical Implementation of the Timing
+// Attack), and the model keeps every run reproducible from its seed.
+// Ref: Kocher, Timing Attacks on Implementations of Diffie-Hellman, RSA, DSS, and Other Systems
+
+use crypto_math::{from_slice, Keypair};
+use montgomery::OperationCount;
+use num::bigint::BigInt;
+use num_traits::ToPrimitive;
+use rand::{Rng, SeedableRng, StdRng};
+use wasm_bindgen::prelude::*;
+
+// Simulated cost of one Montgomery multiplication
+const MULTIPLICATION_COST: u64 = 100;
+// Simulated cost of the extra subtraction at the end of REDC
+const EXTRA_REDUCTION_COST: u64 = 10;
+
+// Bits in each of the toy primes, the modulus fits in 32 bits so R = 2^32 works
+const TOY_PRIME_BITS: u32 = 16;
+
+// Montgomery arithmetic with R = 2^32 that reports whether the final subtraction happened
+#[derive(Clone, Copy, Debug)]
+struct ToyMontgomery {
+    n: u64,
+    // -n^-1 mod 2^32
+    n_prime: u64,
+    // 2^64 mod n
+    r2: u64,
+}
+
+impl ToyMontgomery {
+    fn new(n: u64) -> ToyMontgomery {
+        // Newton's iteration for n^-1 mod 2^32, five steps take 1 correct bit to 32
+        let mut inverse: u64 = 1;
+        for _ in 0..5 {
+            inverse =
+                inverse.wrapping_mul(2u64.wrapping_sub(n.wrapping_mul(inverse))) & 0xffff_ffff;
+        }
+
+        ToyMontgomery {
+            n,
+            n_prime: (1u64 << 32).wrapping_sub(inverse) & 0xffff_ffff,
+            r2: ((1u128 << 64) % n as u128) as u64,
+        }
+    }
+
+    // a * b * R^-1 mod n, and whether the extra reduction was needed
+    fn mul(&self, a: u64, b: u64) -> (u64, bool) {
+        let t = a as u128 * b as u128;
+        let m = (t as u64 & 0xffff_ffff).wrapping_mul(self.n_prime) & 0xffff_ffff;
+        let u = ((t + m as u128 * self.n as u128) >> 32) as u64;
+
+        if u >= self.n {
+            (u - self.n, true)
+        } else {
+            (u, false)
+        }
+    }
+
+    // a * R mod n
+    fn encode(&self, a: u64) -> u64 {
+        self.mul(a % self.n, self.r2).0
+    }
+
+    // Back from a * R mod n to a
+    fn decode(&self, a: u64) -> u64 {
+        self.mul(a, 1).0
+    }
+}
+
+// The victim, a Keypair with 16-bit primes so the attack takes a fraction of a second
+pub fn toy_key(seed: &[u8]) -> Keypair {
+    let mut rng: StdRng = SeedableRng::from_seed(from_slice(seed));
+    let mut other_seed = [0u8; 32];
+    rng.fill(&mut other_seed);
+
+    Keypair::new_toy(seed, &other_seed, TOY_PRIME_BITS)
+}
+
+fn modulus_u64(key: &Keypair) -> u64 {
+    key.modulus().to_u64().unwrap()
+}
+
+// Decrypts c with key's private exponent, returning the plaintext and the simulated running time.
+//
+// The variable-time path is left-to-right square-and-multiply over the bits of d, paying for every
+// extra reduction it happens to need. The hardened path is Keypair::decrypt_number itself, charged
+// for the multiplications and extra reductions its blinded fixed-window exponentiation reports.
+fn decrypt_timed(key: &Keypair, c: u64, hardened: bool) -> (u64, u64) {
+    let mut count = OperationCount::default();
+
+    let plaintext = if hardened {
+        key.decrypt_num_counted(&BigInt::from(c), &mut count)
+            .to_u64()
+            .unwrap()
+    } else {
+        square_and_multiply(key, c, &mut count)
+    };
+
+    (plaintext, cost(&count))
+}
+
+// c^d mod n the textbook way, counting multiplications and extra reductions like the real
+// exponentiation does
+fn square_and_multiply(key: &Keypair, c: u64, count: &mut OperationCount) -> u64 {
+    let d = key.private_exponent().to_u64().unwrap();
+    let mont = ToyMontgomery::new(modulus_u64(key));
+    let c_m = mont.encode(c);
+
+    let mut x = c_m;
+
+    let mut multiply = |a: u64, b: u64| {
+        let (product, extra) = mont.mul(a, b);
+        count.multiplications += 1;
+        if extra {
+            count.extra_reductions += 1;
+        }
+        product
+    };
+
+    for i in (0..bit_length(d) - 1).rev() {
+        x = multiply(x, x);
+
+        if (d >> i) & 1 == 1 {
+            x = multiply(x, c_m);
+        }
+    }
+
+    mont.decode(x)
+}
+
+fn cost(count: &OperationCount) -> u64 {
+    count.multiplications * MULTIPLICATION_COST + count.extra_reductions * EXTRA_REDUCTION_COST
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimingSample {
+    pub ciphertext: u64,
+    pub time: u64,
+}
+
+// Decrypts samples random ciphertexts and records how long each took. noise adds a uniformly
+// random amount in [0, noise] to every measurement, like jitter from the rest of the machine.
+pub fn measure_decryptions(
+    key: &Keypair,
+    samples: usize,
+    noise: u64,
+    hardened: bool,
+    seed: &[u8],
+) -> Vec<TimingSample> {
+    let mut rng: StdRng = SeedableRng::from_seed(from_slice(seed));
+    let n = modulus_u64(key);
+
+    (0..samples)
+        .map(|_| {
+            let ciphertext = rng.gen_range(2, n);
+            let (_, time) = decrypt_timed(key, ciphertext, hardened);
+            let jitter = if noise > 0 {
+                rng.gen_range(0, noise + 1)
+            } else {
+                0
+            };
+
+            TimingSample {
+                ciphertext,
+                time: time + jitter,
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttackResult {
+    // Best guess for d
+    pub recovered_d: u64,
+    // For each bit of d between the leading one and the trailing one: how much better the
+    // timings are explained if the bit is one than if it is zero. Positive means the bit is set.
+    pub bit_differences: Vec<f64>,
+}
+
+// Mean time of the samples where the flag is set minus the mean of the rest
+fn split_difference(samples: &[TimingSample], flags: &[bool]) -> f64 {
+    let (mut slow_total, mut slow_count) = (0.0, 0.0);
+    let (mut fast_total, mut fast_count) = (0.0, 0.0);
+
+    for (sample, &flag) in samples.iter().zip(flags.iter()) {
+        if flag {
+            slow_total += sample.time as f64;
+            slow_count += 1.0;
+        } else {
+            fast_total += sample.time as f64;
+            fast_count += 1.0;
+        }
+    }
+
+    if slow_count > 0.0 && fast_count > 0.0 {
+        slow_total / slow_count - fast_total / fast_count
+    } else {
+        0.0
+    }
+}
+
+// Recovers d one bit at a time from the most significant end. With the bits so far known the
+// attacker can replay the exponentiation for every ciphertext up to the next bit, under both
+// guesses for it. Each guess predicts which ciphertexts need an extra reduction in the squaring
+// that follows, and only the right guess splits the samples into a slow and a fast group. Using
+// the following squaring rather than the multiplication by c keeps the split from being biased by
+// the size of c (Dhem et al., section 4). d is odd, so the last bit is always one, and its bit
+// length is taken as known since in practice it can be read off the average running time.
+pub fn kocher_attack(n: u64, d_bits: usize, samples: &[TimingSample]) -> AttackResult {
+    let mont = ToyMontgomery::new(n);
+
+    let bases: Vec<u64> = samples.iter().map(|s| mont.encode(s.ciphertext)).collect();
+    let mut intermediates = bases.clone();
+
+    let mut recovered_d: u64 = 1;
+    let mut bit_differences = Vec::new();
+
+    for _ in 1..d_bits.saturating_sub(1) {
+        let if_zero: Vec<u64> = intermediates.iter().map(|&x| mont.mul(x, x).0).collect();
+        let if_one: Vec<u64> = if_zero
+            .iter()
+            .zip(bases.iter())
+            .map(|(&x, &c)| mont.mul(x, c).0)
+            .collect();
+
+        let extra_if_zero: Vec<bool> = if_zero.iter().map(|&x| mont.mul(x, x).1).collect();
+        let extra_if_one: Vec<bool> = if_one.iter().map(|&x| mont.mul(x, x).1).collect();
+
+        let difference =
+            split_difference(samples, &extra_if_one) - split_difference(samples, &extra_if_zero);
+        bit_differences.push(difference);
+
+        if difference > 0.0 {
+            recovered_d = (recovered_d << 1) | 1;
+            intermediates = if_one;
+        } else {
+            recovered_d <<= 1;
+            intermediates = if_zero;
+        }
+    }
+
+    if d_bits > 1 {
+        recovered_d = (recovered_d << 1) | 1;
+    }
+
+    AttackResult {
+        recovered_d,
+        bit_differences,
+    }
+}
+
+// Runs the whole experiment against a fresh toy key, once on the variable-time decryption and
+// once on the hardened one, and returns a JSON report for the client to chart.
+#[wasm_bindgen]
+pub fn timing_attack_report(samples: u32, noise: u32, seed: &[u8]) -> String {
+    let key = toy_key(seed);
+    let n = modulus_u64(&key);
+    let d = key.private_exponent().to_u64().unwrap();
+    let d_bits = bit_length(d);
+
+    let leaky = measure_decryptions(&key, samples as usize, noise as u64, false, seed);
+    let hardened = measure_decryptions(&key, samples as usize, noise as u64, true, seed);
+
+    let leaky_result = kocher_attack(n, d_bits, &leaky);
+    let hardened_result = kocher_attack(n, d_bits, &hardened);
+
+    format!(
+        "{{\"n\":\"{}\",\"e\":\"{}\",\"d\":\"{}\",\"d_binary\":\"{}\",\"samples\":{},\"variable_time\":{},\"hardened\":{}}}",
+        n,
+        key.public_exponent(),
+        d,
+        binary(d, d_bits),
+        samples_json(&leaky),
+        result_json(&leaky_result, d, d_bits),
+        result_json(&hardened_result, d, d_bits)
+    )
+}
+
+fn samples_json(samples: &[TimingSample]) -> String {
+    let entries: Vec<String> = samples
+        .iter()
+        .map(|s| {
+            format!(
+                "{{\"ciphertext\":\"{}\",\"time\":{}}}",
+                s.ciphertext, s.time
+            )
+        })
+        .collect();
+
+    format!("[{}]", entries.join(","))
+}
+
+fn result_json(result: &AttackResult, d: u64, d_bits: usize) -> String {
+    let differences: Vec<String> = result
+        .bit_differences
+        .iter()
+        .map(|x| format!("{:.3}", x))
+        .collect();
+
+    format!(
+        "{{\"recovered_d\":\"{}\",\"recovered_bits\":\"{}\",\"correct_bits\":{},\"bit_differences\":[{}]}}",
+        result.recovered_d,
+        binary(result.recovered_d, d_bits),
+        correct_prefix(result.recovered_d, d, d_bits),
+        differences.join(",")
+    )
+}
+
+fn binary(x: u64, bits: usize) -> String {
+    format!("{:0width$b}", x, width = bits)
+}
+
+// How many leading bits of the guess are right, everything after the first mistake is garbage
+fn correct_prefix(guess: u64, actual: u64, bits: usize) -> usize {
+    (0..bits)
+        .rev()
+        .take_while(|&i| (guess >> i) & 1 == (actual >> i) & 1)
+        .count()
+}
+
+fn bit_length(x: u64) -> usize {
+    64 - x.leading_zeros() as usize
+}
+
+#[cfg(test)]
+mod test_timing {
+    use super::*;
+    use crypto_math::{second_test_seed, test_seed};
+
+    fn private_exponent(key: &Keypair) -> u64 {
+        key.private_exponent().to_u64().unwrap()
+    }
+
+    #[test]
+    fn toy_key_decrypts() {
+        let key = toy_key(test_seed());
+        let n = modulus_u64(&key);
+
+        for &m in &[2u64, 72, 12345, n - 1] {
+            let c: u64 = key.encrypt_number(&m.to_string()).parse().unwrap();
+
+            assert_eq!(decrypt_timed(&key, c, false).0, m);
+            assert_eq!(decrypt_timed(&key, c, true).0, m);
+        }
+    }
+
+    #[test]
+    fn hardened_time_is_constant() {
+        let key = toy_key(test_seed());
+        let samples = measure_decryptions(&key, 200, 0, true, second_test_seed());
+        assert!(samples.iter().all(|s| s.time == samples[0].time));
+    }
+
+    #[test]
+    fn hardened_time_is_charged_from_the_exponentiation() {
+        let key = toy_key(test_seed());
+        let mut count = OperationCount::default();
+        key.decrypt_num_counted(&BigInt::from(12345), &mut count);
+
+        assert!(count.multiplications > 0);
+        assert_eq!(decrypt_timed(&key, 12345, true).1, cost(&count));
+    }
+
+    #[test]
+    fn recovers_d_without_noise() {
+        let key = toy_key(test_seed());
+        let d = private_exponent(&key);
+        let samples = measure_decryptions(&key, 3000, 0, false, second_test_seed());
+        let result = kocher_attack(modulus_u64(&key), bit_length(d), &samples);
+        assert_eq!(result.recovered_d, d);
+    }
+
+    #[test]
+    fn recovers_d_with_noise() {
+        let key = toy_key(second_test_seed());
+        let d = private_exponent(&key);
+        let samples = measure_decryptions(&key, 6000, 50, false, test_seed());
+        let result = kocher_attack(modulus_u64(&key), bit_length(d), &samples);
+        assert_eq!(result.recovered_d, d);
+    }
+
+    #[test]
+    fn fails_against_hardened_decryption() {
+        let key = toy_key(test_seed());
+        let d = private_exponent(&key);
+        let samples = measure_decryptions(&key, 3000, 0, true, second_test_seed());
+        let result = kocher_attack(modulus_u64(&key), bit_length(d), &samples);
+        assert!(result.recovered_d != d);
+        assert!(result.bit_differences.iter().all(|&x| x == 0.0));
+        assert_eq!(result.recovered_d, (1 << (bit_length(d) - 1)) | 1);
+    }
+
+    #[test]
+    fn report_is_json() {
+        let report = timing_attack_report(50, 0, test_seed());
+        assert!(report.starts_with("{\"n\":"));
+        assert!(report.ends_with("}}"));
+        assert_eq!(report.matches("\"ciphertext\"").count(), 50);
+        assert!(report.contains("\"variable_time\":{\"recovered_d\""));
+        assert!(report.contains("\"d_binary\":\""));
+    }
+}
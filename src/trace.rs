@@ -0,0 +1,414 @@
+// Worked examples for the UI. Each function here redoes one of the computations in crypto_math and
+// records every intermediate value on the way, returning the lot as JSON so the client can lay it
+// out as a table without reimplementing any of the arithmetic. Numbers are written as strings since
+// they rarely fit in a JavaScript double.
+
+use crypto_math::{from_slice, string_to_number};
+use num::{
+    bigint::{BigInt, RandBigInt},
+    Integer, One, Zero,
+};
+use rand::{SeedableRng, StdRng};
+use wasm_bindgen::prelude::*;
+
+// One row of the extended Euclid table: r = a s + b t, and q the quotient that produced the next row
+struct EuclidRow {
+    quotient: Option<BigInt>,
+    remainder: BigInt,
+    s: BigInt,
+    t: BigInt,
+}
+
+// The whole table for a and b, from (a, 1, 0) and (b, 0, 1) down to remainder 0
+fn euclid_rows(a: &BigInt, b: &BigInt) -> Vec<EuclidRow> {
+    let mut rows = vec![
+        EuclidRow {
+            quotient: None,
+            remainder: a.clone(),
+            s: BigInt::one(),
+            t: BigInt::zero(),
+        },
+        EuclidRow {
+            quotient: None,
+            remainder: b.clone(),
+            s: BigInt::zero(),
+            t: BigInt::one(),
+        },
+    ];
+
+    while !rows[rows.len() - 1].remainder.is_zero() {
+        let last = rows.len() - 1;
+        let q = rows[last - 1].remainder.div_floor(&rows[last].remainder);
+
+        let next = EuclidRow {
+            quotient: None,
+            remainder: &rows[last - 1].remainder - &q * &rows[last].remainder,
+            s: &rows[last - 1].s - &q * &rows[last].s,
+            t: &rows[last - 1].t - &q * &rows[last].t,
+        };
+
+        rows[last].quotient = Some(q);
+        rows.push(next);
+    }
+
+    rows
+}
+
+fn rows_json(rows: &[EuclidRow]) -> String {
+    let entries: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            let quotient = match row.quotient {
+                Some(ref q) => format!("\"{}\"", q),
+                None => "null".to_string(),
+            };
+            format!(
+                "{{\"quotient\":{},\"remainder\":\"{}\",\"s\":\"{}\",\"t\":\"{}\"}}",
+                quotient, row.remainder, row.s, row.t
+            )
+        })
+        .collect();
+
+    format!("[{}]", entries.join(","))
+}
+
+// The extended Euclidean algorithm as a table. The second to last row holds the gcd and the
+// coefficients x, y with a x + b y = gcd.
+// Ref: https://en.wikipedia.org/wiki/Extended_Euclidean_algorithm#Example
+#[wasm_bindgen]
+pub fn extended_gcd_trace(a: &str, b: &str) -> String {
+    let rows = euclid_rows(&string_to_number(a), &string_to_number(b));
+    let result = &rows[rows.len() - 2];
+
+    format!(
+        "{{\"a\":\"{}\",\"b\":\"{}\",\"rows\":{},\"gcd\":\"{}\",\"x\":\"{}\",\"y\":\"{}\"}}",
+        a,
+        b,
+        rows_json(&rows),
+        result.remainder,
+        result.s,
+        result.t
+    )
+}
+
+#[cfg(test)]
+mod test_extended_gcd_trace {
+    use super::*;
+
+    #[test]
+    fn tiny() {
+        assert_eq!(
+            extended_gcd_trace("240", "46"),
+            "{\"a\":\"240\",\"b\":\"46\",\"rows\":[\
+             {\"quotient\":null,\"remainder\":\"240\",\"s\":\"1\",\"t\":\"0\"},\
+             {\"quotient\":\"5\",\"remainder\":\"46\",\"s\":\"0\",\"t\":\"1\"},\
+             {\"quotient\":\"4\",\"remainder\":\"10\",\"s\":\"1\",\"t\":\"-5\"},\
+             {\"quotient\":\"1\",\"remainder\":\"6\",\"s\":\"-4\",\"t\":\"21\"},\
+             {\"quotient\":\"1\",\"remainder\":\"4\",\"s\":\"5\",\"t\":\"-26\"},\
+             {\"quotient\":\"2\",\"remainder\":\"2\",\"s\":\"-9\",\"t\":\"47\"},\
+             {\"quotient\":null,\"remainder\":\"0\",\"s\":\"23\",\"t\":\"-120\"}],\
+             \"gcd\":\"2\",\"x\":\"-9\",\"y\":\"47\"}"
+        );
+    }
+
+    #[test]
+    fn smaller_first() {
+        // The first quotient is 0 and just swaps a and b
+        let trace = extended_gcd_trace("12", "17");
+        assert!(trace.contains("{\"quotient\":\"0\",\"remainder\":\"17\""));
+        assert!(trace.ends_with("\"gcd\":\"1\",\"x\":\"-7\",\"y\":\"5\"}"));
+    }
+}
+
+// mod_inverse worked through the Euclid table for a and m. inverse is null when gcd(a, m) isn't 1,
+// otherwise x reduced into [0, m).
+#[wasm_bindgen]
+pub fn mod_inverse_trace(a: &str, m: &str) -> String {
+    let m_num = string_to_number(m);
+    let rows = euclid_rows(&string_to_number(a), &m_num);
+    let result = &rows[rows.len() - 2];
+
+    let inverse = if result.remainder.is_one() {
+        format!("\"{}\"", result.s.mod_floor(&m_num))
+    } else {
+        "null".to_string()
+    };
+
+    format!(
+        "{{\"a\":\"{}\",\"m\":\"{}\",\"rows\":{},\"gcd\":\"{}\",\"inverse\":{}}}",
+        a,
+        m,
+        rows_json(&rows),
+        result.remainder,
+        inverse
+    )
+}
+
+#[cfg(test)]
+mod test_mod_inverse_trace {
+    use super::*;
+
+    #[test]
+    fn tiny() {
+        // 17 * 2753 = 1 mod 3120, the classic RSA example
+        let trace = mod_inverse_trace("17", "3120");
+        assert!(trace.starts_with("{\"a\":\"17\",\"m\":\"3120\",\"rows\":["));
+        assert!(trace.ends_with("\"gcd\":\"1\",\"inverse\":\"2753\"}"));
+    }
+
+    #[test]
+    fn not_invertible() {
+        assert!(mod_inverse_trace("6", "15").ends_with("\"gcd\":\"3\",\"inverse\":null}"));
+    }
+}
+
+// Left-to-right square-and-multiply: for each bit of the exponent from the top the running result
+// is squared, then multiplied by the base when the bit is 1. Lists the value after each.
+// Ref: https://en.wikipedia.org/wiki/Exponentiation_by_squaring#Basic_method
+fn modpow_steps_json(base: &BigInt, exponent: &BigInt, modulus: &BigInt) -> (String, BigInt) {
+    let bits = exponent.to_str_radix(2);
+    let base = base.mod_floor(modulus);
+    let mut result = BigInt::one().mod_floor(modulus);
+
+    let steps: Vec<String> = bits
+        .chars()
+        .map(|bit| {
+            let squared = &result * &result % modulus;
+            result = if bit == '1' {
+                &squared * &base % modulus
+            } else {
+                squared.clone()
+            };
+
+            let multiplied = if bit == '1' {
+                format!("\"{}\"", result)
+            } else {
+                "null".to_string()
+            };
+            format!(
+                "{{\"bit\":{},\"squared\":\"{}\",\"multiplied\":{}}}",
+                bit, squared, multiplied
+            )
+        })
+        .collect();
+
+    (
+        format!(
+            "{{\"base\":\"{}\",\"exponent\":\"{}\",\"modulus\":\"{}\",\"bits\":\"{}\",\"steps\":[{}],\"result\":\"{}\"}}",
+            base,
+            exponent,
+            modulus,
+            bits,
+            steps.join(","),
+            result
+        ),
+        result,
+    )
+}
+
+// base^exponent mod modulus one squaring at a time
+#[wasm_bindgen]
+pub fn modpow_trace(base: &str, exponent: &str, modulus: &str) -> String {
+    let (json, _) = modpow_steps_json(
+        &string_to_number(base),
+        &string_to_number(exponent),
+        &string_to_number(modulus),
+    );
+    json
+}
+
+#[cfg(test)]
+mod test_modpow_trace {
+    use super::*;
+
+    #[test]
+    fn tiny() {
+        // 3^13 mod 7 with 13 = 1101
+        assert_eq!(
+            modpow_trace("3", "13", "7"),
+            "{\"base\":\"3\",\"exponent\":\"13\",\"modulus\":\"7\",\"bits\":\"1101\",\"steps\":[\
+             {\"bit\":1,\"squared\":\"1\",\"multiplied\":\"3\"},\
+             {\"bit\":1,\"squared\":\"2\",\"multiplied\":\"6\"},\
+             {\"bit\":0,\"squared\":\"1\",\"multiplied\":null},\
+             {\"bit\":1,\"squared\":\"1\",\"multiplied\":\"3\"}],\"result\":\"3\"}"
+        );
+    }
+
+    #[test]
+    fn matches_modpow() {
+        let base = string_to_number("123456789");
+        let exponent = string_to_number("65537");
+        let modulus = string_to_number("1000000007");
+        let (_, result) = modpow_steps_json(&base, &exponent, &modulus);
+
+        assert_eq!(result, base.modpow(&exponent, &modulus));
+    }
+}
+
+// What encrypt does to m, one modpow_trace per byte
+#[wasm_bindgen]
+pub fn encrypt_trace(m: &str, e: &str, n: &str) -> String {
+    let e_num = string_to_number(e);
+    let n_num = string_to_number(n);
+
+    let blocks: Vec<String> = m
+        .bytes()
+        .map(|b| {
+            let (json, _) = modpow_steps_json(&BigInt::from(b), &e_num, &n_num);
+            format!("{{\"byte\":{},\"trace\":{}}}", b, json)
+        })
+        .collect();
+
+    format!("[{}]", blocks.join(","))
+}
+
+#[cfg(test)]
+mod test_encrypt_trace {
+    use super::*;
+    use crypto_math::encrypt;
+
+    #[test]
+    fn matches_encrypt() {
+        let trace = encrypt_trace("Hi", "17", "3233");
+        let ciphertext = encrypt("Hi", "17", "3233");
+
+        assert!(trace.starts_with("[{\"byte\":72,\"trace\":{\"base\":\"72\""));
+        assert_eq!(trace.matches("\"byte\"").count(), 2);
+        for c in ciphertext.split(',').filter(|c| !c.is_empty()) {
+            assert!(trace.contains(&format!("\"result\":\"{}\"}}}}", c)));
+        }
+    }
+}
+
+// miller_rabin with its reasoning shown: n - 1 = 2^s d, then for each base a the sequence
+// a^d, a^2d, ... and whether it proves n composite. Uses the same seed and bases as miller_rabin
+// but stops after rounds of them, or at the first witness.
+// Ref: https://en.wikipedia.org/wiki/Miller%E2%80%93Rabin_primality_test
+#[wasm_bindgen]
+pub fn miller_rabin_trace(n: &str, seed: &[u8], rounds: u32) -> String {
+    let n_num = string_to_number(n);
+    let two = BigInt::from(2);
+
+    if n_num == two {
+        return format!("{{\"n\":\"{}\",\"prime\":true,\"reason\":\"two\"}}", n);
+    }
+    if n_num < two || n_num.is_even() {
+        return format!(
+            "{{\"n\":\"{}\",\"prime\":false,\"reason\":\"even or below two\"}}",
+            n
+        );
+    }
+    // The only odd n below 5, where there is no base in [2, n - 2] to pick
+    if n_num == BigInt::from(3) {
+        return format!("{{\"n\":\"{}\",\"prime\":true,\"reason\":\"three\"}}", n);
+    }
+
+    let n_minus_one = &n_num - BigInt::one();
+    let mut s = 0;
+    let mut d = n_minus_one.clone();
+    while d.is_even() {
+        s += 1;
+        d /= &two;
+    }
+
+    let mut rng: StdRng = SeedableRng::from_seed(from_slice(seed));
+    let mut prime = true;
+    let mut entries = Vec::new();
+
+    for _ in 0..rounds {
+        let a = rng.gen_bigint_range(&two, &n_minus_one);
+
+        if !a.gcd(&n_num).is_one() {
+            entries.push(format!(
+                "{{\"base\":\"{}\",\"sequence\":[],\"verdict\":\"shares the factor {}\"}}",
+                a,
+                a.gcd(&n_num)
+            ));
+            prime = false;
+            break;
+        }
+
+        // a^d, then up to s - 1 squarings stopping at n - 1
+        let mut x = a.modpow(&d, &n_num);
+        let mut sequence = vec![x.clone()];
+        let mut passes = x.is_one() || x == n_minus_one;
+        for _ in 1..s {
+            if passes {
+                break;
+            }
+            x = &x * &x % &n_num;
+            sequence.push(x.clone());
+            passes = x == n_minus_one;
+        }
+
+        let values: Vec<String> = sequence.iter().map(|x| format!("\"{}\"", x)).collect();
+        entries.push(format!(
+            "{{\"base\":\"{}\",\"sequence\":[{}],\"verdict\":\"{}\"}}",
+            a,
+            values.join(","),
+            if passes { "probable prime" } else { "witness" }
+        ));
+
+        if !passes {
+            prime = false;
+            break;
+        }
+    }
+
+    format!(
+        "{{\"n\":\"{}\",\"s\":{},\"d\":\"{}\",\"rounds\":[{}],\"prime\":{}}}",
+        n,
+        s,
+        d,
+        entries.join(","),
+        prime
+    )
+}
+
+#[cfg(test)]
+mod test_miller_rabin_trace {
+    use super::*;
+    use crypto_math::{miller_rabin, test_seed};
+
+    #[test]
+    fn miniscule() {
+        assert_eq!(
+            miller_rabin_trace("2", test_seed(), 5),
+            "{\"n\":\"2\",\"prime\":true,\"reason\":\"two\"}"
+        );
+        assert_eq!(
+            miller_rabin_trace("3", test_seed(), 5),
+            "{\"n\":\"3\",\"prime\":true,\"reason\":\"three\"}"
+        );
+        assert!(miller_rabin_trace("4", test_seed(), 5).contains("\"prime\":false"));
+        assert!(miller_rabin_trace("10", test_seed(), 5).contains("\"prime\":false"));
+    }
+
+    #[test]
+    fn five() {
+        // The smallest n with a base to sample, and then only a = 2 or 3
+        let trace = miller_rabin_trace("5", test_seed(), 5);
+        assert!(trace.starts_with("{\"n\":\"5\",\"s\":2,\"d\":\"1\""));
+        assert!(trace.ends_with("\"prime\":true}"));
+    }
+
+    #[test]
+    fn prime() {
+        // 1000000007 - 1 = 2 * 500000003
+        let trace = miller_rabin_trace("1000000007", test_seed(), 5);
+        assert!(trace.starts_with("{\"n\":\"1000000007\",\"s\":1,\"d\":\"500000003\""));
+        assert_eq!(trace.matches("probable prime").count(), 5);
+        assert!(trace.ends_with("\"prime\":true}"));
+        assert!(miller_rabin("1000000007", test_seed()));
+    }
+
+    #[test]
+    fn carmichael() {
+        // 561 = 3 * 11 * 17 fools the Fermat test for coprime bases but not Miller-Rabin
+        let trace = miller_rabin_trace("561", test_seed(), 50);
+        assert!(trace.starts_with("{\"n\":\"561\",\"s\":4,\"d\":\"35\""));
+        assert!(trace.contains("witness") || trace.contains("shares the factor"));
+        assert!(trace.ends_with("\"prime\":false}"));
+        assert!(!miller_rabin("561", test_seed()));
+    }
+}